@@ -0,0 +1,161 @@
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::encoding::Encoding;
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::palette::Palette;
+use crate::text::{Cell, is_blank};
+use crate::ColorMode;
+
+// the colors of the ibm pc in the order of their sgr codes (30-37 and the
+// same again with bold), which are what ansi art viewers expect.
+const VGA_COLORS: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00], [0xaa, 0x00, 0x00], [0x00, 0xaa, 0x00], [0xaa, 0x55, 0x00],
+    [0x00, 0x00, 0xaa], [0xaa, 0x00, 0xaa], [0x00, 0xaa, 0xaa], [0xaa, 0xaa, 0xaa],
+    [0x55, 0x55, 0x55], [0xff, 0x55, 0x55], [0x55, 0xff, 0x55], [0xff, 0xff, 0x55],
+    [0x55, 0x55, 0xff], [0xff, 0x55, 0xff], [0x55, 0xff, 0xff], [0xff, 0xff, 0xff],
+];
+
+// a classic .ans file: code page 437 text with 16 color sgr codes and a SAUCE
+// record at the end describing it. bright foregrounds are made with bold and
+// bright backgrounds with blink, which viewers show as bright when the iCE
+// colors flag of the record is set.
+pub struct AnsFormatter{
+    color: ColorMode,
+    palette: Palette,
+    title: String,
+    author: String,
+    font: String,
+    attributes: Option<(usize, Option<usize>)>,
+    ice_colors: bool,
+    column: usize,
+    columns: usize,
+    lines: u32,
+    line_buff: String,
+    output_buff: Vec<u8>,
+}
+
+impl AnsFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        // a css font family means nothing to an ansi art viewer
+        let font = if options.font == "monospace" { String::from("IBM VGA") } else { options.font.clone() };
+        Box::new(AnsFormatter{
+            color: options.color,
+            palette: Palette::from(VGA_COLORS.to_vec(), 0),
+            title: options.title.clone(),
+            author: options.author.clone(),
+            font,
+            attributes: None,
+            ice_colors: false,
+            column: 0,
+            columns: 0,
+            lines: 0,
+            line_buff: String::new(),
+            output_buff: Vec::new(),
+        })
+    }
+
+    fn push_attributes(&mut self, foreground: usize, background: Option<usize>){
+        self.line_buff.push_str("\x1b[0");
+        if foreground >= 8 {
+            self.line_buff.push_str(";1");
+        }
+        self.line_buff.push_str(&format!(";{}", 30 + foreground % 8));
+        if let Some(background) = background {
+            if background >= 8 {
+                self.line_buff.push_str(";5");
+                self.ice_colors = true;
+            }
+            self.line_buff.push_str(&format!(";{}", 40 + background % 8));
+        }
+        self.line_buff.push('m');
+    }
+}
+
+impl OutputFormatter for AnsFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        // the record at the end needs the size of the file, so everything is written in finish
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        if self.color != ColorMode::NONE {
+            let foreground = match self.attributes {
+                // keep the current foreground under blank glyphs, it does not show anyway
+                Some((current, _)) if is_blank(&cell.glyph) => current,
+                _ => self.palette.nearest(cell.rgb),
+            };
+            let attributes = (foreground, cell.background.map(|c| self.palette.nearest(c)));
+            if self.attributes != Some(attributes) {
+                self.push_attributes(attributes.0, attributes.1);
+                self.attributes = Some(attributes);
+            }
+        }
+        self.line_buff.push_str(&cell.glyph);
+        self.column += cell.width();
+        Ok(())
+    }
+
+    fn own_palette(&self) -> bool {
+        true
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        if self.attributes.take().is_some() {
+            self.line_buff.push_str("\x1b[0m");
+        }
+        self.line_buff.push_str("\r\n");
+        Encoding::CP437.encode(&self.line_buff, &mut self.output_buff);
+        self.line_buff.clear();
+        self.columns = self.columns.max(self.column);
+        self.column = 0;
+        self.lines += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(&self.output_buff)?;
+        // end of file, so that the record is not shown
+        out.write_all(&[0x1a])?;
+        let mut record = Vec::<u8>::with_capacity(128);
+        record.extend_from_slice(b"SAUCE00");
+        push_field(&mut record, &self.title, 35);
+        push_field(&mut record, &self.author, 20);
+        push_field(&mut record, "", 20);
+        push_field(&mut record, &sauce_date(), 8);
+        record.extend_from_slice(&(self.output_buff.len() as u32).to_le_bytes());
+        // data type 1 (character) and file type 1 (ansi)
+        record.extend_from_slice(&[1, 1]);
+        record.extend_from_slice(&(self.columns.min(u16::MAX as usize) as u16).to_le_bytes());
+        record.extend_from_slice(&(self.lines.min(u16::MAX as u32) as u16).to_le_bytes());
+        record.extend_from_slice(&[0, 0, 0, 0]);
+        // no comments, then the flags
+        record.extend_from_slice(&[0, self.ice_colors as u8]);
+        record.extend(self.font.bytes().take(22).chain(std::iter::repeat(0)).take(22));
+        out.write_all(&record)?;
+        out.flush()
+    }
+}
+
+// SAUCE text fields are code page 437, padded with spaces.
+fn push_field(record: &mut Vec<u8>, text: &str, length: usize){
+    let mut field = Vec::<u8>::new();
+    Encoding::CP437.encode(text, &mut field);
+    field.resize(length, b' ');
+    record.extend_from_slice(&field);
+}
+
+// today's date as CCYYMMDD.
+fn sauce_date() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86400) as i64;
+    // days to a civil date, after Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}{:02}{:02}", year, month, day)
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+use crate::palette::Palette;
+use crate::ramp::Attribute;
+use crate::ColorMode;
+
+pub const SGR_RESET: &str = "\x1b[0m";
+
+// the escape sequence that switches to `attribute`. bold and faint share the
+// code that turns them off.
+pub fn sgr_attribute(attribute: Attribute) -> &'static str {
+    match attribute {
+        Attribute::NORMAL => "\x1b[22m",
+        Attribute::BOLD => "\x1b[22;1m",
+        Attribute::DIM => "\x1b[22;2m",
+    }
+}
+
+// writes the escape sequences for the selected color mode. the colors given to
+// it are expected to be quantized to the palette of the mode already.
+pub struct AnsiColorizer{
+    color: ColorMode,
+    palette: Option<Palette>,
+}
+
+impl AnsiColorizer{
+    pub fn new(color: ColorMode) -> AnsiColorizer {
+        AnsiColorizer{ color, palette: Palette::for_mode(color) }
+    }
+
+    // appends the escape sequence that sets the foreground color to `rgb`.
+    pub fn push_foreground(&self, rgb: [u8; 3], output_buff: &mut String){
+        self.push_color(rgb, false, output_buff);
+    }
+
+    // appends the escape sequence that sets the background color to `rgb`.
+    pub fn push_background(&self, rgb: [u8; 3], output_buff: &mut String){
+        self.push_color(rgb, true, output_buff);
+    }
+
+    fn push_color(&self, rgb: [u8; 3], background: bool, output_buff: &mut String){
+        let [r, g, b] = rgb;
+        let base = if background { 40 } else { 30 };
+        // writing into a String never fails
+        let _ = match (self.color, &self.palette) {
+            (ColorMode::TRUECOLOR, _) => write!(output_buff, "\x1b[{};2;{};{};{}m", base + 8, r, g, b),
+            (ColorMode::ANSI256, Some(palette)) => write!(output_buff, "\x1b[{};5;{}m", base + 8, palette.nearest(rgb)),
+            (ColorMode::ANSI16, Some(palette)) => {
+                let index = palette.nearest(rgb);
+                if index < 8 {
+                    write!(output_buff, "\x1b[{}m", base + index)
+                }else{
+                    write!(output_buff, "\x1b[{}m", base + 60 + index - 8)
+                }
+            },
+            _ => Ok(()),
+        };
+    }
+}
+
+// picks the richest color mode the terminal claims to support.
+pub fn detect_color_mode() -> ColorMode {
+    if std::env::var_os("NO_COLOR").is_some_and(|s| !s.is_empty()) {
+        return ColorMode::NONE;
+    }
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default().to_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorMode::TRUECOLOR;
+    }
+    let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
+    if term.is_empty() || term == "dumb" {
+        ColorMode::NONE
+    }else if term.contains("truecolor") || term.contains("direct") {
+        ColorMode::TRUECOLOR
+    }else if term.contains("256color") {
+        ColorMode::ANSI256
+    }else{
+        ColorMode::ANSI16
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use crate::encoding::Encoding;
+use crate::formatter::{FormatOptions, OutputFormatter, TextFormatter};
+use crate::json::escape_json;
+use crate::text::Cell;
+
+// an asciinema (asciicast v2) recording. every frame is drawn by the TEXT
+// formatter, so -f, -F and --color work as usual, and becomes one output
+// event that moves the cursor home and redraws the screen.
+pub struct AsciicastFormatter{
+    text: Box<dyn OutputFormatter>,
+    screen: Vec<u8>,
+    column: usize,
+    columns: usize,
+    lines: usize,
+    time: f32,
+    events: Vec<String>,
+}
+
+impl AsciicastFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        // recordings are utf-8, whatever --encoding says
+        let mut text_options = options.clone();
+        text_options.encoding = Encoding::UTF8;
+        Box::new(AsciicastFormatter{
+            text: TextFormatter::build(&text_options),
+            screen: Vec::new(),
+            column: 0,
+            columns: 0,
+            lines: 0,
+            time: 0.0,
+            events: Vec::new(),
+        })
+    }
+}
+
+impl OutputFormatter for AsciicastFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, width: u32, height: u32) -> io::Result<()> {
+        // the header needs the size of the terminal, so everything is written in finish
+        self.text.begin(&mut self.screen, width, height)
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.column += cell.width();
+        self.text.cell(&mut self.screen, cell)
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, row: u32) -> io::Result<()> {
+        self.columns = self.columns.max(self.column);
+        self.column = 0;
+        self.lines = self.lines.max(row as usize + 1);
+        self.text.end_of_line(&mut self.screen, row)
+    }
+
+    fn end_of_frame(&mut self, _out: &mut dyn Write, delay: f32) -> io::Result<()> {
+        let screen = String::from_utf8_lossy(&self.screen);
+        // a newline after the last row would scroll the screen by one line
+        let screen = screen.strip_suffix('\n').unwrap_or(&screen).replace('\n', "\r\n");
+        let home = if self.events.is_empty() { "\x1b[2J\x1b[H" } else { "\x1b[H" };
+        let mut data = String::new();
+        escape_json(&format!("{}{}", home, screen), &mut data);
+        self.events.push(format!("[{:.6}, \"o\", {}]", self.time, data));
+        self.screen.clear();
+        self.time += delay;
+        Ok(())
+    }
+
+    fn animated(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{{\"version\": 2, \"width\": {}, \"height\": {}}}", self.columns, self.lines)?;
+        for event in &self.events{
+            writeln!(out, "{}", event)?;
+        }
+        // keeps the last frame on the screen for as long as it should be
+        if self.time > 0.0 {
+            writeln!(out, "[{:.6}, \"o\", \"\"]", self.time)?;
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,120 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter, identifier};
+use crate::text::Cell;
+
+// 1-bit images of the dithered pixels of PIXEL output. like in print, bright
+// pixels are white (0) and dark ones black (1).
+
+////////// PBM ///////////
+
+// a netpbm bitmap, raw (P4) or with --plain the ascii variant (P1).
+pub struct PbmFormatter{
+    plain: bool,
+    row: Vec<bool>,
+}
+
+impl PbmFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(PbmFormatter{
+            plain: options.plain,
+            row: Vec::new(),
+        })
+    }
+}
+
+impl OutputFormatter for PbmFormatter{
+    fn begin(&mut self, out: &mut dyn Write, width: u32, height: u32) -> io::Result<()> {
+        let magic = if self.plain { "P1" } else { "P4" };
+        writeln!(out, "{}", magic)?;
+        writeln!(out, "# made by aRtySt")?;
+        writeln!(out, "{} {}", width, height)
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.row.push(cell.index == 0);
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        if self.plain {
+            // lines of a plain pbm should not be longer than 70 characters
+            for chunk in self.row.chunks(70){
+                let line: String = chunk.iter().map(|black| if *black { '1' } else { '0' }).collect();
+                writeln!(out, "{}", line)?;
+            }
+        }else{
+            out.write_all(&pack_bits(&self.row, false))?;
+        }
+        self.row.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.flush()
+    }
+}
+
+////////// XBM ///////////
+
+// an x bitmap, which is a piece of c source code. the name of the variables
+// is taken from --title.
+pub struct XbmFormatter{
+    name: String,
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+    row: Vec<bool>,
+}
+
+impl XbmFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(XbmFormatter{
+            name: identifier(&options.title, "artyst"),
+            width: 0,
+            height: 0,
+            bytes: Vec::new(),
+            row: Vec::new(),
+        })
+    }
+}
+
+impl OutputFormatter for XbmFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, width: u32, height: u32) -> io::Result<()> {
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.row.push(cell.index == 0);
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.bytes.extend(pack_bits(&self.row, true));
+        self.row.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "#define {}_width {}", self.name, self.width)?;
+        writeln!(out, "#define {}_height {}", self.name, self.height)?;
+        writeln!(out, "static unsigned char {}_bits[] = {{", self.name)?;
+        let lines: Vec<String> = self.bytes.chunks(12)
+            .map(|chunk| chunk.iter().map(|b| format!("0x{:02x}", b)).collect::<Vec<String>>().join(", "))
+            .collect();
+        writeln!(out, "   {}}};", lines.join(",\n   "))?;
+        out.flush()
+    }
+}
+
+// packs a row of pixels into bytes, padding the last one with zeros. pbm puts
+// the leftmost pixel in the highest bit, xbm in the lowest.
+fn pack_bits(row: &[bool], lsb_first: bool) -> Vec<u8> {
+    row.chunks(8).map(|chunk| {
+        chunk.iter().enumerate().fold(0u8, |acc, (k, bit)| {
+            let shift = if lsb_first { k } else { 7 - k };
+            acc | ((*bit as u8) << shift)
+        })
+    }).collect()
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+use imageproc::image::{ImageBuffer, ImageFormat, Luma};
+use crate::ditherer::{ThresholdMap, white_noise};
+use crate::meprintln;
+
+// how far the pixels of a pattern push each other apart, in pixels
+const SIGMA: f32 = 1.5;
+// the generated masks are always the same
+const SEED: u64 = 0x5eed;
+
+// a binary pattern on a torus and how crowded every pixel of it is.
+#[derive(Clone)]
+struct Pattern{
+    size: usize,
+    filter: Vec<f32>,
+    ones: Vec<bool>,
+    energy: Vec<f32>,
+}
+
+impl Pattern{
+    fn new(size: usize) -> Pattern {
+        let mut filter = vec![0.0; size * size];
+        for dy in 0..size{
+            for dx in 0..size{
+                // the distance wraps around so the mask tiles without seams
+                let y = dy.min(size - dy) as f32;
+                let x = dx.min(size - dx) as f32;
+                filter[dy * size + dx] = (-(x * x + y * y) / (2.0 * SIGMA * SIGMA)).exp();
+            }
+        }
+        Pattern{ size, filter, ones: vec![false; size * size], energy: vec![0.0; size * size] }
+    }
+
+    fn set(&mut self, k: usize, on: bool){
+        if self.ones[k] == on {
+            return;
+        }
+        self.ones[k] = on;
+        let sign = if on { 1.0 } else { -1.0 };
+        let n = self.size;
+        let (ky, kx) = (k / n, k % n);
+        for y in 0..n{
+            let dy = (y + n - ky) % n;
+            for x in 0..n{
+                let dx = (x + n - kx) % n;
+                self.energy[y * n + x] += sign * self.filter[dy * n + dx];
+            }
+        }
+    }
+
+    // the one that has the most ones around it
+    fn tightest_cluster(&self) -> usize {
+        self.pick(true, |a, b| a > b)
+    }
+
+    // the zero that has the fewest ones around it
+    fn largest_void(&self) -> usize {
+        self.pick(false, |a, b| a < b)
+    }
+
+    fn pick(&self, on: bool, better: fn(f32, f32) -> bool) -> usize {
+        let mut best: Option<usize> = None;
+        for k in 0..self.ones.len(){
+            if self.ones[k] == on && best.is_none_or(|b| better(self.energy[k], self.energy[b])) {
+                best = Some(k);
+            }
+        }
+        best.expect("the pattern is never all ones or all zeros here")
+    }
+}
+
+// the order in which the pixels of a `size` by `size` blue noise mask turn on,
+// found with the void-and-cluster method of Ulichney. every pixel added is
+// put where it is furthest from the ones already there, so at every level
+// the lit pixels are spread evenly, without the grid of a bayer matrix.
+pub fn void_and_cluster(size: u32) -> Vec<u32> {
+    let n = size as usize;
+    let count = n * n;
+    let mut initial = Pattern::new(n);
+    let mut i = 0;
+    while initial.ones.iter().filter(|o| **o).count() < (count / 10).max(1) {
+        let k = ((white_noise(SEED, 0, i) * count as f32) as usize).min(count - 1);
+        initial.set(k, true);
+        i += 1;
+    }
+    // moves the ones out of the clusters into the voids until they are even
+    for _ in 0..count{
+        let cluster = initial.tightest_cluster();
+        initial.set(cluster, false);
+        let void = initial.largest_void();
+        initial.set(void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let ones = initial.ones.iter().filter(|o| **o).count();
+    let mut ranks = vec![0u32; count];
+    let mut pattern = initial.clone();
+    for rank in (0..ones).rev(){
+        let cluster = pattern.tightest_cluster();
+        pattern.set(cluster, false);
+        ranks[cluster] = rank as u32;
+    }
+    // past half, the largest void of the ones is the tightest cluster of the zeros
+    let mut pattern = initial;
+    for rank in ones..count{
+        let void = pattern.largest_void();
+        pattern.set(void, true);
+        ranks[void] = rank as u32;
+    }
+    ranks
+}
+
+// a blue noise mask of the given size. with a cache file, the mask is read
+// from it when it is there and of the right size, otherwise it is made and
+// saved there for the next time.
+pub fn blue_noise(size: u32, cache: Option<&str>) -> Result<ThresholdMap, String> {
+    if let Some(path) = cache {
+        if Path::new(path).exists() {
+            let (ranks, width, height) = read_ranks(path)?;
+            if width == size && height == size {
+                return Ok(ThresholdMap::from_ranks(ranks, size, size));
+            }
+            meprintln!("WARNING: the mask in {} is {}x{}, not {}x{}. it will be made again.", path, width, height, size, size);
+        }
+    }
+    let ranks = void_and_cluster(size);
+    if let Some(path) = cache {
+        if let Err(e) = write_ranks(path, &ranks, size) {
+            meprintln!("WARNING: could not save the mask to {}: {}", path, e);
+        }
+    }
+    Ok(ThresholdMap::from_ranks(ranks, size, size))
+}
+
+// a threshold mask drawn as a grayscale image, darker pixels turning on first.
+pub fn load_mask(path: &str) -> Result<ThresholdMap, String> {
+    let (ranks, width, height) = read_ranks(path)?;
+    Ok(ThresholdMap::from_ranks(ranks, width, height))
+}
+
+fn read_ranks(path: &str) -> Result<(Vec<u32>, u32, u32), String> {
+    let image = imageproc::image::open(path).map_err(|e| e.to_string())?.into_luma16();
+    let (width, height) = image.dimensions();
+    let values: Vec<u16> = image.into_raw();
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by_key(|k| values[*k]);
+    let mut ranks = vec![0u32; values.len()];
+    for (rank, k) in order.into_iter().enumerate(){
+        ranks[k] = rank as u32;
+    }
+    Ok((ranks, width, height))
+}
+
+// saved in 16 bits, so that masks of up to 256x256 keep every level
+fn write_ranks(path: &str, ranks: &[u32], size: u32) -> Result<(), String> {
+    let top = (ranks.len().max(2) - 1) as u32;
+    let values: Vec<u16> = ranks.iter().map(|r| (r * 65535 / top) as u16).collect();
+    let image = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(size, size, values).ok_or("the mask has the wrong size")?;
+    image.save_with_format(path, ImageFormat::Png).map_err(|e| e.to_string())
+}
@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::palette::Palette;
+use crate::petscii::c64_palette;
+use crate::text::Cell;
+
+// the screen as the c64 keeps it in memory: 1000 bytes of screen codes
+// (screen ram), 1000 bytes of foreground colors (color ram), then the
+// border and background color. only makes sense for PETSCII output.
+pub struct C64Formatter{
+    palette: Palette,
+    screen_ram: Vec<u8>,
+    color_ram: Vec<u8>,
+    background: u8,
+}
+
+impl C64Formatter{
+    pub fn build(_options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(C64Formatter{
+            palette: c64_palette(),
+            screen_ram: Vec::new(),
+            color_ram: Vec::new(),
+            background: 0,
+        })
+    }
+}
+
+impl OutputFormatter for C64Formatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.screen_ram.push(cell.index as u8);
+        self.color_ram.push(self.palette.nearest(cell.rgb) as u8);
+        if let Some(background) = cell.background {
+            self.background = self.palette.nearest(background) as u8;
+        }
+        Ok(())
+    }
+
+    fn own_palette(&self) -> bool {
+        true
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(&self.screen_ram)?;
+        out.write_all(&self.color_ram)?;
+        out.write_all(&[self.background, self.background])?;
+        out.flush()
+    }
+}
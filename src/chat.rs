@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::palette::Palette;
+use crate::text::{Cell, is_blank};
+use crate::{ColorMode, meprintln};
+
+// the 16 colors every irc client knows, followed by the 83 colors mirc added
+// later (16 to 98).
+const MIRC_COLORS: [[u8; 3]; 99] = [
+    [0xff, 0xff, 0xff], [0x00, 0x00, 0x00], [0x00, 0x00, 0x7f], [0x00, 0x93, 0x00], [0xff, 0x00, 0x00], [0x7f, 0x00, 0x00],
+    [0x9c, 0x00, 0x9c], [0xfc, 0x7f, 0x00], [0xff, 0xff, 0x00], [0x00, 0xfc, 0x00], [0x00, 0x93, 0x93], [0x00, 0xff, 0xff],
+    [0x00, 0x00, 0xfc], [0xff, 0x00, 0xff], [0x7f, 0x7f, 0x7f], [0xd2, 0xd2, 0xd2],
+    [0x47, 0x00, 0x00], [0x47, 0x21, 0x00], [0x47, 0x47, 0x00], [0x32, 0x47, 0x00], [0x00, 0x47, 0x00], [0x00, 0x47, 0x2c],
+    [0x00, 0x47, 0x47], [0x00, 0x27, 0x47], [0x00, 0x00, 0x47], [0x2e, 0x00, 0x47], [0x47, 0x00, 0x47], [0x47, 0x00, 0x2a],
+    [0x74, 0x00, 0x00], [0x74, 0x3a, 0x00], [0x74, 0x74, 0x00], [0x51, 0x74, 0x00], [0x00, 0x74, 0x00], [0x00, 0x74, 0x49],
+    [0x00, 0x74, 0x74], [0x00, 0x40, 0x74], [0x00, 0x00, 0x74], [0x4b, 0x00, 0x74], [0x74, 0x00, 0x74], [0x74, 0x00, 0x45],
+    [0xb5, 0x00, 0x00], [0xb5, 0x63, 0x00], [0xb5, 0xb5, 0x00], [0x7d, 0xb5, 0x00], [0x00, 0xb5, 0x00], [0x00, 0xb5, 0x71],
+    [0x00, 0xb5, 0xb5], [0x00, 0x63, 0xb5], [0x00, 0x00, 0xb5], [0x75, 0x00, 0xb5], [0xb5, 0x00, 0xb5], [0xb5, 0x00, 0x6b],
+    [0xff, 0x00, 0x00], [0xff, 0x8c, 0x00], [0xff, 0xff, 0x00], [0xb2, 0xff, 0x00], [0x00, 0xff, 0x00], [0x00, 0xff, 0xa0],
+    [0x00, 0xff, 0xff], [0x00, 0x8c, 0xff], [0x00, 0x00, 0xff], [0xa5, 0x00, 0xff], [0xff, 0x00, 0xff], [0xff, 0x00, 0x98],
+    [0xff, 0x59, 0x59], [0xff, 0xb4, 0x59], [0xff, 0xff, 0x71], [0xcf, 0xff, 0x60], [0x6f, 0xff, 0x6f], [0x65, 0xff, 0xc9],
+    [0x6d, 0xff, 0xff], [0x59, 0xb4, 0xff], [0x59, 0x59, 0xff], [0xc4, 0x59, 0xff], [0xff, 0x66, 0xff], [0xff, 0x59, 0xbc],
+    [0xff, 0x9c, 0x9c], [0xff, 0xd3, 0x9c], [0xff, 0xff, 0x9c], [0xe2, 0xff, 0x9c], [0x9c, 0xff, 0x9c], [0x9c, 0xff, 0xdb],
+    [0x9c, 0xff, 0xff], [0x9c, 0xd3, 0xff], [0x9c, 0x9c, 0xff], [0xdc, 0x9c, 0xff], [0xff, 0x9c, 0xff], [0xff, 0x94, 0xd3],
+    [0x00, 0x00, 0x00], [0x13, 0x13, 0x13], [0x28, 0x28, 0x28], [0x36, 0x36, 0x36], [0x4d, 0x4d, 0x4d], [0x65, 0x65, 0x65],
+    [0x81, 0x81, 0x81], [0x9f, 0x9f, 0x9f], [0xbc, 0xbc, 0xbc], [0xe2, 0xe2, 0xe2], [0xff, 0xff, 0xff],
+];
+
+#[derive(PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+enum Markup{
+    MIRC,
+    BBCODE,
+}
+
+// colored art for chats and forums. runs of equally colored characters share
+// one color code, and the lines are packed into messages of at most
+// --max-length bytes. an irc message is a single line, so there every line is
+// a message of its own; forum posts take as many lines as fit and are
+// separated by an empty line. lines too long for a message are split.
+pub struct ChatFormatter{
+    markup: Markup,
+    color: ColorMode,
+    palette: Palette,
+    max_length: usize,
+    run: Option<String>,
+    line_buff: String,
+    message: String,
+    messages: Vec<String>,
+    split_lines: bool,
+}
+
+impl ChatFormatter{
+    fn build(options: &FormatOptions, markup: Markup, max_length: usize) -> Box<dyn OutputFormatter> {
+        // --color 16 keeps to the colors every irc client has
+        let colors = if options.color == ColorMode::ANSI16 { &MIRC_COLORS[..16] } else { &MIRC_COLORS[..] };
+        Box::new(ChatFormatter{
+            markup,
+            color: options.color,
+            palette: Palette::from(colors.to_vec(), 0),
+            max_length: options.max_length.unwrap_or(max_length),
+            run: None,
+            line_buff: String::new(),
+            message: String::new(),
+            messages: Vec::new(),
+            split_lines: false,
+        })
+    }
+
+    pub fn build_mirc(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        // irc lines are at most 512 bytes, and the command and channel name take up some of them
+        ChatFormatter::build(options, Markup::MIRC, 400)
+    }
+
+    pub fn build_bbcode(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        ChatFormatter::build(options, Markup::BBCODE, 60000)
+    }
+
+    // the code that starts a run of the colors of `cell`.
+    fn opening(&self, cell: &Cell) -> String {
+        match self.markup {
+            Markup::MIRC => {
+                let mut code = format!("\x03{:02}", self.palette.nearest(cell.rgb));
+                if let Some(background) = cell.background {
+                    code.push_str(&format!(",{:02}", self.palette.nearest(background)));
+                }
+                code
+            },
+            // bbcode has no standard way of setting the background
+            Markup::BBCODE => {
+                let [r, g, b] = cell.rgb;
+                format!("[color=#{:02x}{:02x}{:02x}]", r, g, b)
+            },
+        }
+    }
+
+    // the code that ends the run started by `opening` before the run `next`
+    // starts, or before the line ends.
+    fn closing(&self, opening: &str, next: Option<&str>) -> &'static str {
+        match self.markup {
+            // a plain ^C resets both colors. it is only needed when a background is left behind
+            Markup::MIRC => if opening.contains(',') && next.is_some_and(|n| !n.contains(',')) { "\x03" } else { "" },
+            Markup::BBCODE => "[/color]",
+        }
+    }
+
+    fn push_glyph(&self, glyph: &str, after_code: bool, output_buff: &mut String){
+        match self.markup {
+            Markup::MIRC => {
+                // a comma right after a color code would be taken for a background color
+                if after_code && glyph.starts_with(',') {
+                    output_buff.push_str("\x02\x02");
+                }
+                output_buff.push_str(glyph);
+            },
+            Markup::BBCODE => {
+                for c in glyph.chars(){
+                    output_buff.push(c);
+                    // keeps things like "[i]" in the art from being taken for tags
+                    if c == '[' {
+                        output_buff.push_str("[b][/b]");
+                    }
+                }
+            },
+        }
+    }
+
+    // the text of a cell, with the codes that switch from the current run to `run`.
+    fn piece(&self, run: &Option<String>, glyph: &str) -> String {
+        let mut piece = String::new();
+        let mut after_code = false;
+        if run != &self.run {
+            if let Some(current) = &self.run {
+                piece.push_str(self.closing(current, run.as_deref()));
+            }
+            if let Some(next) = run {
+                piece.push_str(next);
+                after_code = true;
+            }
+        }
+        self.push_glyph(glyph, after_code, &mut piece);
+        piece
+    }
+
+    fn close_run(&mut self){
+        if let Some(current) = self.run.take() {
+            self.line_buff.push_str(self.closing(&current, None));
+        }
+    }
+
+    fn push_line(&mut self, line: String){
+        let fits = self.message.len() + 1 + line.len() <= self.max_length;
+        if self.markup == Markup::MIRC || (!self.message.is_empty() && !fits) {
+            self.messages.push(std::mem::take(&mut self.message));
+        }
+        if !self.message.is_empty() {
+            self.message.push('\n');
+        }
+        self.message.push_str(&line);
+    }
+}
+
+impl OutputFormatter for ChatFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        // the color of a blank character does not show, so it joins the current run
+        let run = if self.color == ColorMode::NONE || (is_blank(&cell.glyph) && cell.background.is_none()) {
+            self.run.clone()
+        }else{
+            Some(self.opening(cell))
+        };
+        let mut piece = self.piece(&run, &cell.glyph);
+        let closing = run.as_ref().map_or(0, |r| self.closing(r, None).len());
+        if !self.line_buff.is_empty() && self.line_buff.len() + piece.len() + closing > self.max_length {
+            // the line does not fit in a message, the rest of it goes to the next one
+            self.close_run();
+            let line = std::mem::take(&mut self.line_buff);
+            self.push_line(line);
+            self.split_lines = true;
+            piece = self.piece(&run, &cell.glyph);
+        }
+        self.line_buff.push_str(&piece);
+        self.run = run;
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.close_run();
+        let line = std::mem::take(&mut self.line_buff);
+        self.push_line(line);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if !self.message.is_empty() {
+            self.messages.push(std::mem::take(&mut self.message));
+        }
+        if self.split_lines {
+            meprintln!("WARNING: some lines are longer than --max-length={} bytes and have been split. try a smaller width or fewer colors.", self.max_length);
+        }
+        let separator = if self.markup == Markup::MIRC { "\n" } else { "\n\n" };
+        let messages: Vec<&str> = self.messages.iter().map(String::as_str).filter(|m| !m.is_empty()).collect();
+        writeln!(out, "{}", messages.join(separator))?;
+        out.flush()
+    }
+}
@@ -1,31 +1,137 @@
 
 use crate::matrix::Matrix;
+use crate::kernel::Kernel;
 
 pub trait Ditherer{
     fn dither(&self, output: &mut Matrix<f32>);
 }
 
+// how the pixels are quantized: one after the other, spreading the error with
+// a kernel, all at once against a threshold map, or class by class spreading
+// the error to the classes that come later.
+#[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum DitherMethod{
+    KERNEL(Kernel, ScanOrder),
+    ORDERED(ThresholdMap),
+    DOT(Matrix<u32>),
+}
+
+// the order the kernel ditherers visit the pixels in. SERPENTINE goes right to
+// left on every other row and COLUMN goes down the columns, the kernel being
+// mirrored or turned to match so the error always goes to pixels not visited yet.
+#[derive(Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ScanOrder{
+    RASTER,
+    SERPENTINE,
+    COLUMN,
+}
+
+impl ScanOrder{
+    // every pixel as (y, x), and whether it is on a line scanned backwards
+    fn pixels(self, width: u32, height: u32) -> impl Iterator<Item = (u32, u32, bool)> {
+        let (lines, length) = if self == ScanOrder::COLUMN { (width, height) } else { (height, width) };
+        (0..lines).flat_map(move |line| {
+            let backwards = self == ScanOrder::SERPENTINE && line % 2 == 1;
+            (0..length).map(move |k| {
+                let k = if backwards { length - 1 - k } else { k };
+                match self {
+                    ScanOrder::COLUMN => (k, line, false),
+                    _ => (line, k, backwards),
+                }
+            })
+        })
+    }
+
+    // the offset of a kernel weight for a pixel scanned this way
+    fn offset(self, off_x: i32, off_y: i32, backwards: bool) -> (i32, i32) {
+        match self {
+            ScanOrder::COLUMN => (off_y, off_x),
+            _ if backwards => (-off_x, off_y),
+            _ => (off_x, off_y),
+        }
+    }
+}
+
 pub struct OnOffKernelDitherer{
     threshold: f32,
     weights: Vec<(i32, i32, f32)>,
+    scan: ScanOrder,
 }
 
 pub struct InterpolatingKernelDitherer{
     inter_points: Vec<f32>,
     mid_points: Vec<f32>,
     weights: Vec<(i32, i32, f32)>,
+    scan: ScanOrder,
 }
 
-impl OnOffKernelDitherer{
-    pub fn from(threshold: f32, origin: (i32, i32), factors: Matrix<f32>) -> OnOffKernelDitherer {
-        let mut weights = Vec::<(i32, i32, f32)>::with_capacity((factors.get_width() * factors.get_height()).try_into().unwrap());
-        for x in 0..factors.get_height(){
-            for y in 0..factors.get_width(){
-                let val = factors.get(x,  y).unwrap();
-                weights.push( ( (x as i32) - origin.0, (y as i32) - origin.1, val ) );
-            }
+// the weights of a kernel as (x offset, y offset, factor) from its origin,
+// which is given as (column, row).
+fn kernel_weights(origin: (i32, i32), factors: Matrix<f32>) -> Vec<(i32, i32, f32)> {
+    let mut weights = Vec::<(i32, i32, f32)>::with_capacity((factors.get_width() * factors.get_height()).try_into().unwrap());
+    for y in 0..factors.get_height(){
+        for x in 0..factors.get_width(){
+            let val = factors.get(y,  x).unwrap();
+            weights.push( ( (x as i32) - origin.0, (y as i32) - origin.1, val ) );
         }
-        OnOffKernelDitherer{ threshold, weights }
+    }
+    weights
+}
+
+// the value each interpolation point stands for when the error is worked out
+fn mid_points(inter_points: &[f32]) -> Vec<f32> {
+    let mut start = 0.0;
+    let mut mid_points: Vec<f32> = Vec::with_capacity(inter_points.len());
+    for point in inter_points.iter().skip(1){
+        let mid = (point + start) / 2.0;
+        mid_points.push(mid); 
+        start = *point;
+    }
+    mid_points.push((1.0 - start) / 2.0);
+    mid_points
+}
+
+// the index of the last interpolation point `val` reaches, and the value it
+// is quantized to.
+fn interpolate(val: f32, inter_points: &[f32], mid_points: &[f32]) -> (i32, f32) {
+    let mut index = -1;
+    let mut transform = 0.0;
+    for i in 0..inter_points.len(){
+        if val >= inter_points[i]{
+            index += 1;
+            transform = mid_points[i];
+            continue;
+        }
+        break;
+    }
+    (index, transform)
+}
+
+// adds the error of the pixel at (y, x) to the pixels around it.
+fn spread_error(input: &mut Matrix<f32>, y: u32, x: u32, error: f32, weights: &[(i32, i32, f32)], scan: ScanOrder, backwards: bool){
+    for (off_x, off_y, factor) in weights{
+        if *factor == 0.0 {continue;}
+        let (off_x, off_y) = scan.offset(*off_x, *off_y, backwards);
+        let nx = (x as i32) + off_x;
+        let ny = (y as i32) + off_y;
+        if nx < 0 || ny < 0 {continue;}
+        let val = input.get(
+            ny.try_into().unwrap(),
+            nx.try_into().unwrap())
+            .unwrap_or(-1.0);
+        if val == -1.0 {continue;}
+        let _ = input.set(ny.try_into().unwrap(), nx.try_into().unwrap(), val + error * factor);
+        // Since nx and ny have an offset, out of bound access is almost bound to happen.
+        // We ignore Err result because Matrix<T>::get and Matrix::set automatically refuse 
+        // to touch out of bound locations. 
+    }
+}
+
+impl OnOffKernelDitherer{
+    pub fn from(threshold: f32, origin: (i32, i32), factors: Matrix<f32>, scan: ScanOrder) -> OnOffKernelDitherer {
+        OnOffKernelDitherer{ threshold, weights: kernel_weights(origin, factors), scan }
     }
     // unused
     //pub fn new(threshold: f32, weights: Vec<(i32, i32, f32)>) -> OnOffKernelDitherer {
@@ -34,23 +140,10 @@ impl OnOffKernelDitherer{
 }
 
 impl InterpolatingKernelDitherer {
-    pub fn from(inter_points: Vec<f32>, origin: (i32, i32), factors: Matrix<f32>) -> InterpolatingKernelDitherer {
-        let mut weights = Vec::<(i32, i32, f32)>::with_capacity((factors.get_width() * factors.get_height()).try_into().unwrap());
-        for x in 0..factors.get_height(){
-            for y in 0..factors.get_width(){
-                let val = factors.get(x,  y).unwrap();
-                weights.push( ( (x as i32) - origin.0, (y as i32) - origin.1, val ) );
-            }
-        }
-        let mut start = 0.0;
-        let mut mid_points: Vec<f32> = Vec::with_capacity(inter_points.len());
-        for i in 1..inter_points.len(){
-            let mid = (inter_points[i] + start) / 2.0;
-            mid_points.push(mid); 
-            start = inter_points[i];
-        }
-        mid_points.push((1.0 - start) / 2.0);
-        InterpolatingKernelDitherer{ inter_points, mid_points, weights }
+    pub fn from(inter_points: Vec<f32>, origin: (i32, i32), factors: Matrix<f32>, scan: ScanOrder) -> InterpolatingKernelDitherer {
+        let weights = kernel_weights(origin, factors);
+        let mid_points = mid_points(&inter_points);
+        InterpolatingKernelDitherer{ inter_points, mid_points, weights, scan }
     }
     // unused
     //pub fn new(inter_points: Vec<f32>, weights: Vec<(i32, i32, f32)>) -> InterpolatingKernelDitherer {
@@ -69,31 +162,15 @@ impl InterpolatingKernelDitherer {
 impl Ditherer for OnOffKernelDitherer {
     fn dither(&self, output: &mut Matrix<f32>){
         let mut input = output.clone();
-        for y in 0..output.get_height(){
-            for x in 0..output.get_width(){
-                let val_origi = input.get(y, x).unwrap();
-                let val_trans = if val_origi > self.threshold { 1.0 }else{ 0.0 };
-                output.set(y, x, val_trans).expect("Unexpected error"); // it is unexpected because
-                                                                        // x and y are defined to
-                                                                        // be within the bounds of
-                                                                        // matrix dimensions.
-                let error = val_origi - val_trans;
-                for (off_x, off_y, factor) in &self.weights{
-                    if *factor == 0.0 {continue;}
-                    let nx = (x as i32) + off_x;
-                    let ny = (y as i32) + off_y;
-                    if nx < 0 || ny < 0 {continue;}
-                    let val = input.get(
-                        ny.try_into().unwrap(),
-                        nx.try_into().unwrap())
-                        .unwrap_or(-1.0);
-                    if val == -1.0 {continue;}
-                    let _ = input.set(ny.try_into().unwrap(), nx.try_into().unwrap(), val + error * factor);
-                    // Since nx and ny have an offset, out of bound access is almost bound to happen.
-                    // We ignore Err result because Matrix<T>::get and Matrix::set automatically refuse 
-                    // to touch out of bound locations. 
-                }
-            }
+        for (y, x, backwards) in self.scan.pixels(output.get_width(), output.get_height()){
+            let val_origi = input.get(y, x).unwrap();
+            let val_trans = if val_origi > self.threshold { 1.0 }else{ 0.0 };
+            output.set(y, x, val_trans).expect("Unexpected error"); // it is unexpected because
+                                                                    // x and y are defined to
+                                                                    // be within the bounds of
+                                                                    // matrix dimensions.
+            let error = val_origi - val_trans;
+            spread_error(&mut input, y, x, error, &self.weights, self.scan, backwards);
         }
     }
 }
@@ -101,45 +178,220 @@ impl Ditherer for OnOffKernelDitherer {
 impl Ditherer for InterpolatingKernelDitherer {
     fn dither(&self, output: &mut Matrix<f32>){
         let mut input = output.clone();
+        for (y, x, backwards) in self.scan.pixels(output.get_width(), output.get_height()){
+            let val_origi = input.get(y, x).unwrap();
+
+            let (index, val_trans) = interpolate(val_origi, &self.inter_points, &self.mid_points);
+
+            output.set(y, x, index as f32).expect("Unexpected error."); // it is unexpected
+                                                                        // because x and y are
+                                                                        // defined to be within
+                                                                        // the bounds of matrix
+                                                                        // dimensions.
+            let error = val_origi - val_trans;
+            spread_error(&mut input, y, x, error, &self.weights, self.scan, backwards);
+        }
+    }
+}
+
+////////// ORDERED ///////////
+
+// the thresholds of an ordered ditherer, between 0 and 1. a map is tiled over
+// the image, noise gives every pixel a threshold of its own that only depends
+// on where the pixel is and on the seed.
+#[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ThresholdMap{
+    TILED(Matrix<f32>),
+    NOISE(u64),
+}
+
+impl ThresholdMap{
+    // the bayer matrix of the given order, 2^order pixels on a side.
+    pub fn bayer(order: u32) -> ThresholdMap {
+        let mut map = vec![0u32];
+        let mut size = 1;
+        for _ in 0..order{
+            let mut next = vec![0u32; 4 * size * size];
+            for y in 0..size{
+                for x in 0..size{
+                    let v = 4 * map[y * size + x];
+                    next[y * 2 * size + x] = v;
+                    next[y * 2 * size + x + size] = v + 2;
+                    next[(y + size) * 2 * size + x] = v + 3;
+                    next[(y + size) * 2 * size + x + size] = v + 1;
+                }
+            }
+            map = next;
+            size *= 2;
+        }
+        ThresholdMap::from_ranks(map, size as u32, size as u32)
+    }
+
+    // a halftone screen of round dots `size` pixels apart that grow from
+    // the middle of the cell outwards.
+    pub fn clustered(size: u32) -> ThresholdMap {
+        let n = size as usize;
+        let mut spots: Vec<(f32, usize)> = (0..n * n).map(|k| {
+            let x = ((k % n) as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let y = ((k / n) as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            (-(x * x + y * y), k)
+        }).collect();
+        spots.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        let mut ranks = vec![0u32; n * n];
+        for (rank, (_, k)) in spots.into_iter().enumerate(){
+            ranks[k] = rank as u32;
+        }
+        ThresholdMap::from_ranks(ranks, size, size)
+    }
+
+    // the order in which the pixels of a map turn on, spread evenly over 0 to 1.
+    pub fn from_ranks(ranks: Vec<u32>, width: u32, height: u32) -> ThresholdMap {
+        let count = (width * height) as f32;
+        let values = ranks.into_iter().map(|r| (r as f32 + 0.5) / count).collect();
+        ThresholdMap::TILED(Matrix::<f32>::from(values, width, height))
+    }
+
+    pub fn at(&self, y: u32, x: u32) -> f32 {
+        match self {
+            ThresholdMap::TILED(map) => map.get(y % map.get_height(), x % map.get_width()).unwrap(),
+            ThresholdMap::NOISE(seed) => white_noise(*seed, y, x),
+        }
+    }
+}
+
+// a number between 0 and 1 that only depends on the seed and the position.
+// it is the splitmix64 hash of the two.
+pub fn white_noise(seed: u64, y: u32, x: u32) -> f32 {
+    let mut z = seed ^ ((y as u64) << 32 | x as u64).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// compares every pixel against its threshold in the map instead of spreading
+// the error to its neighbours, so no pixel depends on any other. in on/off
+// mode the map is centered on `threshold`. interpolation points start the
+// intervals of the levels, as they do for the kernel ditherers, and a pixel
+// falls to one of the two levels whose interval centers are around it,
+// depending on how far between them it is.
+pub struct OrderedDitherer{
+    map: ThresholdMap,
+    centers: Option<Vec<f32>>,
+    threshold: f32,
+}
+
+impl OrderedDitherer{
+    pub fn onoff(threshold: f32, map: ThresholdMap) -> OrderedDitherer {
+        OrderedDitherer{ map, centers: None, threshold }
+    }
+
+    pub fn interpolating(inter_points: Vec<f32>, map: ThresholdMap) -> OrderedDitherer {
+        // the interval of the last level runs up to 1
+        let ends = inter_points.iter().skip(1).chain([&1.0]);
+        let centers = inter_points.iter().zip(ends).map(|(low, high)| (low + high) / 2.0).collect();
+        OrderedDitherer{ map, centers: Some(centers), threshold: 0.5 }
+    }
+
+    fn quantize(&self, val: f32, limit: f32) -> f32 {
+        match &self.centers {
+            None => if val + limit - 0.5 > self.threshold { 1.0 } else { 0.0 },
+            Some(centers) => {
+                let index = centers.iter().filter(|c| val >= **c).count().max(1) - 1;
+                if index + 1 < centers.len() {
+                    let (low, high) = (centers[index], centers[index + 1]);
+                    if (val - low) / (high - low) > limit {
+                        return (index + 1) as f32;
+                    }
+                }
+                index as f32
+            },
+        }
+    }
+}
+
+impl Ditherer for OrderedDitherer {
+    fn dither(&self, output: &mut Matrix<f32>){
         for y in 0..output.get_height(){
             for x in 0..output.get_width(){
-                let val_origi = input.get(y, x).unwrap();
-
-                let mut index = -1;
-                let mut transform = 0.0;
-                for i in 0..self.inter_points.len(){
-                    if val_origi >= self.inter_points[i]{
-                        index += 1;
-                        transform = self.mid_points[i];
+                let val = output.get(y, x).unwrap();
+                let _ = output.set(y, x, self.quantize(val, self.map.at(y, x)));
+            }
+        }
+    }
+}
+
+////////// DOT DIFFUSION ///////////
+
+// the dot diffusion of Knuth. the class matrix is tiled over the image and the
+// pixels are quantized class by class, each passing its error on to those of
+// its eight neighbours that are in a later class, the ones beside it getting
+// twice as much as the ones on the corners. all the pixels of a class could
+// be done at the same time.
+pub struct DotDiffusionDitherer{
+    classes: Matrix<u32>,
+    inter_points: Option<(Vec<f32>, Vec<f32>)>,
+    threshold: f32,
+}
+
+impl DotDiffusionDitherer{
+    pub fn onoff(threshold: f32, classes: Matrix<u32>) -> DotDiffusionDitherer {
+        DotDiffusionDitherer{ classes, inter_points: None, threshold }
+    }
+
+    pub fn interpolating(inter_points: Vec<f32>, classes: Matrix<u32>) -> DotDiffusionDitherer {
+        let mid_points = mid_points(&inter_points);
+        DotDiffusionDitherer{ classes, inter_points: Some((inter_points, mid_points)), threshold: 0.5 }
+    }
+
+    fn class(&self, y: u32, x: u32) -> u32 {
+        self.classes.get(y % self.classes.get_height(), x % self.classes.get_width()).unwrap()
+    }
+}
+
+impl Ditherer for DotDiffusionDitherer {
+    fn dither(&self, output: &mut Matrix<f32>){
+        let mut input = output.clone();
+        let (width, height) = (output.get_width(), output.get_height());
+        let mut pixels: Vec<(u32, u32, u32)> = Vec::with_capacity((width * height) as usize);
+        for y in 0..height{
+            for x in 0..width{
+                pixels.push((self.class(y, x), y, x));
+            }
+        }
+        pixels.sort_by_key(|p| p.0);
+
+        let mut neighbours = Vec::<(u32, u32, f32)>::with_capacity(8);
+        for (class, y, x) in pixels{
+            let val_origi = input.get(y, x).unwrap();
+            let (index, val_trans) = match &self.inter_points {
+                Some((inter_points, mid_points)) => interpolate(val_origi, inter_points, mid_points),
+                None => if val_origi > self.threshold { (1, 1.0) } else { (0, 0.0) },
+            };
+            let _ = output.set(y, x, index as f32);
+
+            neighbours.clear();
+            for off_y in -1..=1{
+                for off_x in -1..=1{
+                    let ny = y as i32 + off_y;
+                    let nx = x as i32 + off_x;
+                    if (off_x == 0 && off_y == 0) || ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
                         continue;
                     }
-                    break;
-                }
-
-                let val_trans = transform;
-
-                output.set(y, x, index as f32).expect("Unexpected error."); // it is unexpected
-                                                                            // because x and y are
-                                                                            // defined to be within
-                                                                            // the bounds of matrix
-                                                                            // dimensions.
-                let error = val_origi - val_trans;
-                for (off_x, off_y, factor) in &self.weights{
-                    if *factor == 0.0 {continue;}
-                    let nx = (x as i32) + off_x;
-                    let ny = (y as i32) + off_y;
-                    if nx < 0 || ny < 0 {continue;}
-                    let val = input.get(
-                        ny.try_into().unwrap(),
-                        nx.try_into().unwrap())
-                        .unwrap_or(-1.0);
-                    if val == -1.0 {continue;}
-                    let _ = input.set(ny.try_into().unwrap(), nx.try_into().unwrap(), val + error * factor); 
-                    // Since nx and ny have an offset, out of bound access is almost bound to happen.
-                    // We ignore Err result because Matrix<T>::get and Matrix::set automatically refuse 
-                    // to touch out of bound locations. 
+                    if self.class(ny as u32, nx as u32) > class {
+                        let weight = if off_x == 0 || off_y == 0 { 2.0 } else { 1.0 };
+                        neighbours.push((ny as u32, nx as u32, weight));
+                    }
                 }
             }
+            // the last classes have no one to pass the error to and lose it
+            let total: f32 = neighbours.iter().map(|n| n.2).sum();
+            let error = val_origi - val_trans;
+            for (ny, nx, weight) in &neighbours{
+                let val = input.get(*ny, *nx).unwrap();
+                let _ = input.set(*ny, *nx, val + error * weight / total);
+            }
         }
     }
 }
@@ -0,0 +1,47 @@
+use crate::palette::Palette;
+
+// emoji together with their mean color, taken over the pixels they cover in
+// common color emoji fonts. only emoji that are shown as emoji (two columns
+// wide) without a variation selector are in here.
+pub const EMOJI: [(char, [u8; 3]); 97] = [
+    // red
+    ('🍎', [200, 40, 40]), ('🍓', [210, 50, 60]), ('🌹', [190, 40, 50]), ('🍒', [180, 30, 40]),
+    ('🟥', [244, 67, 54]), ('🔴', [230, 60, 50]), ('🍅', [225, 70, 50]), ('🎈', [220, 50, 60]),
+    ('🚒', [200, 60, 55]), ('🥩', [190, 80, 80]), ('🍉', [190, 90, 80]),
+    // orange
+    ('🍊', [245, 145, 30]), ('🟧', [255, 152, 0]), ('🎃', [240, 130, 30]), ('🥕', [240, 125, 40]),
+    ('🦊', [225, 120, 60]), ('🏀', [220, 110, 50]), ('🔶', [250, 150, 40]), ('🍑', [250, 150, 110]),
+    // yellow
+    ('🍋', [250, 215, 60]), ('🟨', [253, 216, 53]), ('🌻', [220, 170, 40]), ('🌕', [240, 210, 110]),
+    ('⭐', [250, 200, 50]), ('🍌', [245, 210, 80]), ('🧀', [250, 200, 80]), ('🐥', [250, 200, 70]),
+    ('😀', [250, 200, 70]), ('🌝', [235, 200, 100]), ('💛', [250, 200, 50]),
+    // green
+    ('🍏', [140, 200, 60]), ('🥦', [90, 150, 60]), ('🌲', [50, 120, 60]), ('🌳', [80, 140, 60]),
+    ('🍀', [70, 160, 60]), ('🟩', [124, 179, 66]), ('🥒', [110, 160, 60]), ('🥬', [120, 180, 80]),
+    ('🐸', [120, 180, 70]), ('🌵', [90, 160, 80]), ('🥝', [130, 150, 60]), ('🌿', [90, 160, 70]),
+    ('🐢', [110, 150, 70]), ('💚', [100, 180, 60]),
+    // blue
+    ('🟦', [30, 136, 229]), ('🔵', [40, 120, 220]), ('💙', [50, 130, 220]), ('🌊', [70, 140, 200]),
+    ('🐳', [90, 160, 210]), ('🧊', [170, 210, 240]), ('💧', [90, 170, 230]), ('🫐', [70, 80, 150]),
+    ('🐋', [70, 110, 160]), ('🔷', [60, 140, 220]), ('🌀', [70, 150, 220]), ('🧿', [40, 90, 180]),
+    // purple
+    ('🟪', [156, 39, 176]), ('🟣', [150, 60, 170]), ('💜', [150, 70, 190]), ('🍇', [120, 60, 140]),
+    ('🍆', [110, 60, 130]), ('🔮', [160, 100, 200]), ('👾', [130, 80, 170]),
+    // pink
+    ('🌸', [250, 180, 200]), ('🐷', [245, 160, 170]), ('💗', [240, 100, 150]), ('🎀', [240, 90, 130]),
+    ('🌷', [230, 90, 120]), ('🌺', [230, 90, 110]), ('💖', [240, 110, 160]),
+    // brown
+    ('🟫', [121, 85, 72]), ('🟤', [130, 90, 70]), ('🍫', [110, 70, 50]), ('🐻', [150, 100, 60]),
+    ('🥔', [190, 150, 100]), ('🍪', [190, 140, 90]), ('🌰', [140, 80, 50]), ('🥥', [140, 100, 70]),
+    ('🍩', [200, 140, 110]), ('🥜', [200, 150, 90]), ('☕', [150, 110, 90]), ('🍞', [220, 170, 110]),
+    // white and gray
+    ('⬜', [240, 240, 240]), ('⚪', [235, 235, 235]), ('🥚', [240, 235, 220]), ('🍚', [230, 230, 225]),
+    ('🐑', [220, 220, 215]), ('🐼', [150, 150, 150]), ('🗿', [130, 130, 130]), ('🐘', [150, 160, 170]),
+    ('🐨', [160, 160, 165]), ('🌑', [90, 90, 100]), ('🎱', [60, 60, 60]),
+    // black
+    ('⬛', [20, 20, 20]), ('⚫', [40, 40, 40]), ('🖤', [40, 40, 40]), ('🎩', [50, 50, 55]),
+];
+
+pub fn emoji_palette() -> Palette {
+    Palette::from(EMOJI.iter().map(|(_, rgb)| *rgb).collect(), 0)
+}
@@ -0,0 +1,61 @@
+// the upper half (0x80..0xFF) of code page 437, the character set of the ibm
+// pc. the lower half is ascii, as far as text files are concerned.
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+                          ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+                          αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+// a shading ramp made of code page 437 characters, in increasing brightness.
+pub const CP437_RAMP: &str = " ·-─┼═╪╬░▒▓█";
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Encoding{
+    UTF8,
+    CP437,
+}
+
+impl Encoding{
+    // characters that do not exist in the encoding are written as '?'.
+    pub fn encode(&self, text: &str, output_buff: &mut Vec<u8>){
+        match self {
+            Encoding::UTF8 => output_buff.extend_from_slice(text.as_bytes()),
+            Encoding::CP437 => output_buff.extend(text.chars().map(|c| cp437_byte(c).unwrap_or(b'?'))),
+        }
+    }
+}
+
+pub fn cp437_byte(c: char) -> Option<u8> {
+    if c.is_ascii() {
+        return Some(c as u8);
+    }
+    CP437_HIGH.chars().position(|h| h == c).map(|i| 0x80 + i as u8)
+}
+
+pub fn cp437_char(byte: u8) -> char {
+    if byte.is_ascii() {
+        byte as char
+    }else{
+        CP437_HIGH.chars().nth((byte - 0x80) as usize).unwrap()
+    }
+}
+
+// lets --chars pick characters from code page 437. "CP437" alone is the
+// built-in shading ramp and "CP437:b0 b1 b2 db" lists the bytes in hex.
+pub fn parse_cp437_chars(text: &str) -> Result<Option<String>, String> {
+    let Some(rest) = text.strip_prefix("CP437").or_else(|| text.strip_prefix("cp437")) else {
+        return Ok(None);
+    };
+    if rest.is_empty() {
+        return Ok(Some(String::from(CP437_RAMP)));
+    }
+    let Some(rest) = rest.strip_prefix(':') else {
+        return Ok(None);
+    };
+    let mut chars = String::new();
+    for byte in rest.split([' ', ',']).filter(|b| !b.is_empty()){
+        match u8::from_str_radix(byte.trim_start_matches("0x"), 16) {
+            Ok(b) => chars.push(cp437_char(b)),
+            Err(_) => return Err(format!("{} is not a byte written in hex", byte)),
+        }
+    }
+    Ok(Some(chars))
+}
@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use crate::text::{QUADRANTS, sextant_char, octant_char};
+
+// a fixed width font made of bitmaps. every glyph is `width * height` pixels,
+// row by row.
+#[derive(Clone)]
+pub struct BitmapFont{
+    width: u32,
+    height: u32,
+    glyphs: HashMap<char, Vec<bool>>,
+    // the block characters, as (rows, bit pattern) of a 2 column grid
+    blocks: HashMap<char, (u32, u32)>,
+}
+
+// the built-in font. each glyph is 5 pixels wide and 9 high (7 above the
+// baseline and 2 below), the most significant bit being the leftmost pixel.
+// it is drawn into a 6x12 cell so that there is some space around it.
+const BUILTIN_WIDTH: u32 = 6;
+const BUILTIN_HEIGHT: u32 = 12;
+const BUILTIN_TOP: u32 = 2;
+// BDF fonts with glyphs larger than this many pixels, or with sizes and
+// offsets beyond MAX_BDF_SIDE, are refused rather than allocated
+const MAX_BDF_AREA: i32 = 256 * 256;
+const MAX_BDF_SIDE: i32 = 1 << 16;
+const BUILTIN_GLYPHS: [(char, [u8; 9]); 94] = [
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100, 0b00000, 0b00000]),
+    ('"', [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010, 0b00000, 0b00000]),
+    ('$', [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100, 0b00000, 0b00000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011, 0b00000, 0b00000]),
+    ('&', [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101, 0b00000, 0b00000]),
+    ('\'', [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010, 0b00000, 0b00000]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000, 0b00000, 0b00000]),
+    ('*', [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000, 0b00000, 0b00000]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100, 0b00100, 0b01000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000, 0b00000, 0b00000]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000, 0b00000]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00000, 0b00000]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00000, 0b00000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100, 0b00000, 0b00000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000, 0b00000]),
+    (';', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00100, 0b01000, 0b00000]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00000, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000, 0b00000, 0b00000]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100, 0b00000, 0b00000]),
+    ('@', [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110, 0b00000, 0b00000]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110, 0b00000, 0b00000]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100, 0b00000, 0b00000]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100, 0b00000, 0b00000]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001, 0b00000, 0b00000]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101, 0b00000, 0b00000]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001, 0b00000, 0b00000]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110, 0b00000, 0b00000]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000, 0b00000]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010, 0b00000, 0b00000]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110, 0b00000, 0b00000]),
+    ('\\', [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000, 0b00000, 0b00000]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110, 0b00000, 0b00000]),
+    ('^', [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111, 0b00000]),
+    ('`', [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('a', [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('b', [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110, 0b00000, 0b00000]),
+    ('c', [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('d', [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('e', [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000, 0b00000]),
+    ('f', [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000, 0b00000, 0b00000]),
+    ('g', [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b10001, 0b01110]),
+    ('h', [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('i', [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('j', [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('k', [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b00000, 0b00000]),
+    ('l', [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('m', [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('n', [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('o', [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('p', [0b00000, 0b00000, 0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('q', [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b00001, 0b00001]),
+    ('r', [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('s', [0b00000, 0b00000, 0b01111, 0b10000, 0b01110, 0b00001, 0b11110, 0b00000, 0b00000]),
+    ('t', [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110, 0b00000, 0b00000]),
+    ('u', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000, 0b00000]),
+    ('v', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000, 0b00000]),
+    ('w', [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010, 0b00000, 0b00000]),
+    ('x', [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000, 0b00000]),
+    ('y', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01111, 0b00001, 0b10001, 0b01110]),
+    ('z', [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000, 0b00000]),
+    ('{', [0b00011, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00011, 0b00000, 0b00000]),
+    ('|', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('}', [0b11000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b11000, 0b00000, 0b00000]),
+    ('~', [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000]),
+];
+
+impl BitmapFont{
+    pub fn builtin() -> BitmapFont {
+        let mut glyphs = HashMap::<char, Vec<bool>>::with_capacity(BUILTIN_GLYPHS.len());
+        for (c, rows) in BUILTIN_GLYPHS{
+            let mut glyph = vec![false; (BUILTIN_WIDTH * BUILTIN_HEIGHT) as usize];
+            for (y, row) in rows.iter().enumerate(){
+                for x in 0..5{
+                    if row & (0b10000 >> x) != 0 {
+                        glyph[((BUILTIN_TOP + y as u32) * BUILTIN_WIDTH + x) as usize] = true;
+                    }
+                }
+            }
+            glyphs.insert(c, glyph);
+        }
+        BitmapFont::from(BUILTIN_WIDTH, BUILTIN_HEIGHT, glyphs)
+    }
+
+    fn from(width: u32, height: u32, glyphs: HashMap<char, Vec<bool>>) -> BitmapFont {
+        let mut blocks = HashMap::<char, (u32, u32)>::new();
+        // finer grids go first so that a character that is also a coarser
+        // pattern (e.g. '▀') ends up with the coarser, equivalent one
+        for charnum in 0..256{
+            blocks.insert(octant_char(charnum), (4, charnum));
+        }
+        for charnum in 0..64{
+            blocks.insert(sextant_char(charnum), (3, charnum));
+        }
+        for (charnum, c) in QUADRANTS.iter().enumerate(){
+            blocks.insert(*c, (2, charnum as u32));
+        }
+        blocks.remove(&' ');
+        BitmapFont{ width, height, glyphs, blocks }
+    }
+
+    // loads a .bdf or a .psf (version 1 or 2) font.
+    pub fn load(path: &str) -> Result<BitmapFont, String> {
+        let mut file = File::options().read(true).open(path).map_err(|e| format!("cannot open {} for reading: {}", path, e))?;
+        let mut bytes = Vec::<u8>::new();
+        file.read_to_end(&mut bytes).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        if bytes.starts_with(&[0x72, 0xb5, 0x4a, 0x86]) || bytes.starts_with(&[0x36, 0x04]) {
+            BitmapFont::load_psf(&bytes)
+        }else if bytes.starts_with(b"STARTFONT") {
+            BitmapFont::load_bdf(&String::from_utf8_lossy(&bytes))
+        }else{
+            Err(format!("{} is neither a BDF nor a PSF font", path))
+        }
+    }
+
+    fn load_psf(bytes: &[u8]) -> Result<BitmapFont, String> {
+        let truncated = || String::from("the PSF font is truncated");
+        let read_u32 = |at: usize| -> Result<u32, String> {
+            bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(truncated)
+        };
+        let (header_size, count, glyph_size, width, height, has_table, psf2) = if *bytes.first().ok_or_else(truncated)? == 0x72 {
+            (read_u32(8)? as usize, read_u32(16)? as usize, read_u32(20)? as usize, read_u32(28)?, read_u32(24)?, read_u32(12)? & 1 != 0, true)
+        }else{
+            let mode = *bytes.get(2).ok_or_else(truncated)?;
+            let height = *bytes.get(3).ok_or_else(truncated)? as u32;
+            (4, if mode & 0x01 != 0 { 512 } else { 256 }, height as usize, 8, height, mode & 0x06 != 0, false)
+        };
+        if width == 0 || height == 0 {
+            return Err(String::from("the PSF font has glyphs of zero size"));
+        }
+        let row_size = width.div_ceil(8) as usize;
+        // check the header against the file before anything is allocated from it
+        if (height as usize).checked_mul(row_size).is_none_or(|size| size > glyph_size) {
+            return Err(String::from("the PSF font has glyphs larger than their bitmaps"));
+        }
+        let glyphs_end = count.checked_mul(glyph_size).and_then(|size| size.checked_add(header_size));
+        if glyphs_end.is_none_or(|end| end > bytes.len()) {
+            return Err(truncated());
+        }
+        let mut bitmaps = Vec::<Vec<bool>>::with_capacity(count);
+        for i in 0..count{
+            let start = header_size + i * glyph_size;
+            let data = &bytes[start..start + glyph_size];
+            let mut glyph = vec![false; width as usize * height as usize];
+            for y in 0..height as usize{
+                for x in 0..width as usize{
+                    let byte = data[y * row_size + x / 8];
+                    glyph[y * width as usize + x] = byte & (0x80 >> (x % 8)) != 0;
+                }
+            }
+            bitmaps.push(glyph);
+        }
+
+        // without a unicode table the glyphs are simply numbered by their code points
+        let mut glyphs = HashMap::<char, Vec<bool>>::with_capacity(count);
+        if !has_table {
+            for (i, glyph) in bitmaps.into_iter().enumerate(){
+                if let Some(c) = char::from_u32(i as u32) {
+                    glyphs.insert(c, glyph);
+                }
+            }
+            return Ok(BitmapFont::from(width, height, glyphs));
+        }
+        let mut at = header_size + count * glyph_size;
+        for glyph in bitmaps{
+            // every entry lists single code points, then sequences (which are skipped), then a terminator
+            let mut in_sequence = false;
+            if psf2 {
+                let end = bytes[at.min(bytes.len())..].iter().position(|b| *b == 0xff).ok_or_else(truncated)? + at;
+                for (k, part) in bytes[at..end].split(|b| *b == 0xfe).enumerate(){
+                    if k > 0 { break; }
+                    for c in String::from_utf8_lossy(part).chars(){
+                        glyphs.insert(c, glyph.clone());
+                    }
+                }
+                at = end + 1;
+            }else{
+                loop {
+                    let b = bytes.get(at..at + 2).ok_or_else(truncated)?;
+                    let value = u16::from_le_bytes([b[0], b[1]]);
+                    at += 2;
+                    match value {
+                        0xffff => break,
+                        0xfffe => in_sequence = true,
+                        _ if in_sequence => (),
+                        _ => {
+                            if let Some(c) = char::from_u32(value as u32) {
+                                glyphs.insert(c, glyph.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(BitmapFont::from(width, height, glyphs))
+    }
+
+    fn load_bdf(text: &str) -> Result<BitmapFont, String> {
+        let numbers = |rest: &str| -> Result<Vec<i32>, String> {
+            rest.split_whitespace().map(|n| n.parse::<i32>().map_err(|_| format!("{} is not a valid number in the BDF font", n))).collect()
+        };
+        let mut bounding_box: Option<(i32, i32, i32, i32)> = None;
+        let mut glyphs = HashMap::<char, Vec<bool>>::new();
+        let mut encoding: i32 = -1;
+        let mut bbx = (0, 0, 0, 0);
+        let mut bitmap: Option<Vec<Vec<u8>>> = None;
+        for line in text.lines(){
+            let line = line.trim();
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let n = numbers(rest)?;
+                    if n.len() != 4 || n[0] <= 0 || n[1] <= 0 || n.iter().any(|v| v.abs() > MAX_BDF_SIDE) {
+                        return Err(String::from("invalid FONTBOUNDINGBOX in the BDF font"));
+                    }
+                    if n[0].checked_mul(n[1]).is_none_or(|area| area > MAX_BDF_AREA) {
+                        return Err(String::from("the glyphs of the BDF font are too large"));
+                    }
+                    bounding_box = Some((n[0], n[1], n[2], n[3]));
+                },
+                "ENCODING" => encoding = *numbers(rest)?.first().unwrap_or(&-1),
+                "BBX" => {
+                    let n = numbers(rest)?;
+                    if n.len() != 4 || n.iter().any(|v| v.abs() > MAX_BDF_SIDE) {
+                        return Err(String::from("invalid BBX in the BDF font"));
+                    }
+                    bbx = (n[0], n[1], n[2], n[3]);
+                },
+                "BITMAP" => bitmap = Some(Vec::new()),
+                "ENDCHAR" => {
+                    let (fw, fh, fx, fy) = bounding_box.ok_or("BDF font has no FONTBOUNDINGBOX")?;
+                    let rows = bitmap.take().unwrap_or_default();
+                    let c = match u32::try_from(encoding).ok().and_then(char::from_u32) {
+                        Some(c) => c,
+                        None => continue,
+                    };
+                    let (w, h, x_off, y_off) = bbx;
+                    let left = x_off - fx;
+                    let top = (fh + fy) - (h + y_off);
+                    let mut glyph = vec![false; (fw * fh) as usize];
+                    for (r, row) in rows.iter().enumerate(){
+                        for col in 0..w{
+                            let (x, y) = (left + col, top + r as i32);
+                            let on = row.get((col / 8) as usize).is_some_and(|byte| byte & (0x80 >> (col % 8)) != 0);
+                            if on && x >= 0 && y >= 0 && x < fw && y < fh {
+                                glyph[(y * fw + x) as usize] = true;
+                            }
+                        }
+                    }
+                    glyphs.insert(c, glyph);
+                },
+                _ => {
+                    if let Some(rows) = bitmap.as_mut() {
+                        // split the bytes, a row of anything but hex digits must give an error, not a panic
+                        let row = line.as_bytes().chunks_exact(2)
+                            .map(|pair| std::str::from_utf8(pair).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+                            .collect::<Option<Vec<u8>>>()
+                            .ok_or_else(|| format!("{} is not a valid bitmap row in the BDF font", line))?;
+                        rows.push(row);
+                    }
+                }
+            }
+        }
+        let (fw, fh, _, _) = bounding_box.ok_or("BDF font has no FONTBOUNDINGBOX")?;
+        Ok(BitmapFont::from(fw as u32, fh as u32, glyphs))
+    }
+
+    pub fn get_width(&self) -> u32 { self.width }
+    pub fn get_height(&self) -> u32 { self.height }
+
+    // the bitmap of `c`. braille and block characters the font does not have
+    // are drawn from their dot patterns, anything else that is missing is
+    // drawn as an empty box.
+    pub fn render(&self, c: char) -> Vec<bool> {
+        if let Some(glyph) = self.glyphs.get(&c) {
+            return glyph.clone();
+        }
+        let (w, h) = (self.width, self.height);
+        let mut glyph = vec![false; (w * h) as usize];
+        let mut fill = |x1: u32, x2: u32, y1: u32, y2: u32| {
+            for y in y1..y2{
+                for x in x1..x2{
+                    glyph[(y * w + x) as usize] = true;
+                }
+            }
+        };
+        if ('\u{2800}'..='\u{28ff}').contains(&c) {
+            let passes = [(0,0,0), (0,1,1), (0,2,2), (1,0,3), (1,1,4), (1,2,5), (0,3,6), (1,3,7)];
+            let charnum = c as u32 - 0x2800;
+            let dot = (w / 3).max(1);
+            for (dx, dy, shift) in passes{
+                if charnum & (1 << shift) != 0 {
+                    let x = dx * w / 2 + (w / 2).saturating_sub(dot) / 2;
+                    let y = dy * h / 4 + (h / 4).saturating_sub(dot) / 2;
+                    fill(x, (x + dot).min(w), y, (y + dot).min(h));
+                }
+            }
+        }else if let Some((rows, charnum)) = self.blocks.get(&c) {
+            for bit in 0..(2 * rows){
+                if charnum & (1 << bit) != 0 {
+                    let (dx, dy) = (bit % 2, bit / 2);
+                    fill(dx * w / 2, (dx + 1) * w / 2, dy * h / rows, (dy + 1) * h / rows);
+                }
+            }
+        }else if !c.is_whitespace() && w > 2 && h > 4 {
+            fill(1, w - 1, 2, 3);
+            fill(1, w - 1, h - 2, h - 1);
+            fill(1, 2, 2, h - 1);
+            fill(w - 2, w - 1, 2, h - 1);
+        }
+        glyph
+    }
+}
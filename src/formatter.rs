@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use crate::template::Template;
+use crate::text::{Cell, is_blank};
+use crate::html::HtmlFormatter;
+use crate::svg::SvgFormatter;
+use crate::png::PngFormatter;
+use crate::json::JsonFormatter;
+use crate::asciicast::AsciicastFormatter;
+use crate::ans::AnsFormatter;
+use crate::c64::C64Formatter;
+use crate::bitmap::{PbmFormatter, XbmFormatter};
+use crate::source::SourceFormatter;
+use crate::chat::ChatFormatter;
+use crate::printer::PrinterFormatter;
+use crate::encoding::Encoding;
+use crate::font::BitmapFont;
+use crate::ansi::{AnsiColorizer, SGR_RESET, sgr_attribute};
+use crate::ramp::{Attribute, Stack};
+use crate::{ColorMode, ThreshOption};
+
+pub type FormatterBuilder = fn(&FormatOptions) -> Box<dyn OutputFormatter>;
+pub type FmtMap = HashMap<&'static str, FormatterBuilder>;
+
+// everything the user can tell the formatters from the command line.
+#[derive(Clone)]
+pub struct FormatOptions{
+    pub fmt: Template,
+    pub fmt_ln: Template,
+    pub color: ColorMode,
+    pub font: String,
+    pub font_size: f32,
+    pub line_height: f32,
+    pub background: String,
+    pub foreground: String,
+    pub bitmap_font: Option<BitmapFont>,
+    pub json_cells: bool,
+    pub encoding: Encoding,
+    pub title: String,
+    pub author: String,
+    pub plain: bool,
+    pub max_length: Option<usize>,
+    pub backspace: bool,
+    // how the art was made, for the formatters that record it
+    pub out_type: String,
+    pub chars: Vec<char>,
+    pub stacks: Vec<Stack>,
+    pub kernel: Option<String>,
+    pub threshold: ThreshOption,
+}
+
+// the cells are fed to a formatter row by row:
+//   begin, ((cell* end_of_line)* end_of_frame)*, finish
+// width and height are given in cells, not in matrix entries. only formatters
+// that say they are animated get more than one frame, the others get the
+// first frame of an animation.
+pub trait OutputFormatter{
+    fn begin(&mut self, out: &mut dyn Write, width: u32, height: u32) -> io::Result<()>;
+    fn cell(&mut self, out: &mut dyn Write, cell: &Cell) -> io::Result<()>;
+    fn end_of_line(&mut self, out: &mut dyn Write, row: u32) -> io::Result<()>;
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()>;
+
+    fn end_of_frame(&mut self, _out: &mut dyn Write, _delay: f32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn animated(&self) -> bool {
+        false
+    }
+
+    // whether an empty line follows the art when it is printed to the
+    // terminal, as plain text output always had.
+    fn trailing_newline(&self) -> bool {
+        false
+    }
+
+    // whether the formatter maps the colors of the image to a palette of
+    // its own. its cells then keep the colors of the image instead of being
+    // snapped to the terminal palette of --color first.
+    fn own_palette(&self) -> bool {
+        false
+    }
+}
+
+// turns a title into a name that can be used as a variable in source code.
+pub fn identifier(text: &str, default: &str) -> String {
+    let mut name: String = text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if name.is_empty() {
+        name = String::from(default);
+    }else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+////////// NOTE FOR CONTRIBUTERS //////////
+//// new output formats go here. Write ////
+//// a struct that implements Output-  ////
+//// Formatter and add a builder for it////
+//// to the HashMap below.             ////
+///////////////////////////////////////////
+
+pub fn get_formatters() -> FmtMap {
+    HashMap::from([
+                  ("TEXT", TextFormatter::build as FormatterBuilder),
+                  ("HTML", HtmlFormatter::build),
+                  ("SVG", SvgFormatter::build),
+                  ("PNG", PngFormatter::build),
+                  ("JSON", JsonFormatter::build),
+                  ("ASCIICAST", AsciicastFormatter::build),
+                  ("ANS", AnsFormatter::build),
+                  ("C64", C64Formatter::build),
+                  ("PBM", PbmFormatter::build),
+                  ("XBM", XbmFormatter::build),
+                  ("C", SourceFormatter::build_c),
+                  ("RUST", SourceFormatter::build_rust),
+                  ("SHELL", SourceFormatter::build_shell),
+                  ("MIRC", ChatFormatter::build_mirc),
+                  ("BBCODE", ChatFormatter::build_bbcode),
+                  ("PRINTER", PrinterFormatter::build),
+    ])
+}
+
+////////// TEXT ///////////
+
+// plain text, shaped by the -f and -F format strings and written in the
+// encoding given by --encoding.
+// with --color the characters are colored using ansi escape sequences. an
+// escape is only emitted where the color of a visible character changes. the
+// bold and faint attributes of an --attr-ramp are written the same way.
+pub struct TextFormatter{
+    fmt: Template,
+    fmt_ln: Template,
+    color: ColorMode,
+    encoding: Encoding,
+    colorizer: AnsiColorizer,
+    run_color: Option<[u8; 3]>,
+    run_background: Option<[u8; 3]>,
+    run_attribute: Attribute,
+    line_buff: String,
+    output_buff: String,
+    encoded_buff: Vec<u8>,
+}
+
+impl TextFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(TextFormatter{
+            fmt: options.fmt.clone(),
+            fmt_ln: options.fmt_ln.clone(),
+            color: options.color,
+            encoding: options.encoding,
+            colorizer: AnsiColorizer::new(options.color),
+            run_color: None,
+            run_background: None,
+            run_attribute: Attribute::NORMAL,
+            line_buff: String::new(),
+            output_buff: String::new(),
+            encoded_buff: Vec::new(),
+        })
+    }
+}
+
+impl OutputFormatter for TextFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn trailing_newline(&self) -> bool {
+        true
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        if self.color != ColorMode::NONE && self.run_color != Some(cell.rgb) && !is_blank(&cell.glyph) {
+            self.colorizer.push_foreground(cell.rgb, &mut self.line_buff);
+            self.run_color = Some(cell.rgb);
+        }
+        if let Some(background) = cell.background {
+            if self.color != ColorMode::NONE && self.run_background != Some(background) {
+                self.colorizer.push_background(background, &mut self.line_buff);
+                self.run_background = Some(background);
+            }
+        }
+        if self.run_attribute != cell.attribute && !is_blank(&cell.glyph) {
+            self.line_buff.push_str(sgr_attribute(cell.attribute));
+            self.run_attribute = cell.attribute;
+        }
+        self.fmt.render_cell(cell, &mut self.line_buff);
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, out: &mut dyn Write, row: u32) -> io::Result<()> {
+        let attributed = std::mem::replace(&mut self.run_attribute, Attribute::NORMAL) != Attribute::NORMAL;
+        if self.run_color.take().is_some() | self.run_background.take().is_some() | attributed {
+            self.line_buff.push_str(SGR_RESET);
+        }
+        self.output_buff.clear();
+        self.fmt_ln.render_line(&self.line_buff, row, &mut self.output_buff);
+        self.line_buff.clear();
+        self.encoded_buff.clear();
+        self.encoding.encode(&self.output_buff, &mut self.encoded_buff);
+        out.write_all(&self.encoded_buff)
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.flush()
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::text::{Cell, is_blank};
+use crate::palette::parse_color;
+use crate::{ColorMode, meprintln};
+
+// a standalone html page with the art inside a <pre> block.
+// with --color every run of equally colored characters is wrapped in a <span>.
+pub struct HtmlFormatter{
+    font: String,
+    font_size: f32,
+    line_height: f32,
+    background: [u8; 3],
+    foreground: [u8; 3],
+    color: ColorMode,
+    run_color: Option<([u8; 3], Option<[u8; 3]>)>,
+    line_buff: String,
+}
+
+impl HtmlFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        let background = parse_color(&options.background).unwrap_or_else(|| {
+            meprintln!("WARNING: HTML output only understands colors written as #rgb or #rrggbb. black is used as the background.");
+            [0, 0, 0]
+        });
+        let foreground = parse_color(&options.foreground).unwrap_or_else(|| {
+            meprintln!("WARNING: HTML output only understands colors written as #rgb or #rrggbb. white is used as the foreground.");
+            [255, 255, 255]
+        });
+        // the font ends up inside the <style> block, so anything that could
+        // close or escape the string, the rule or the element is dropped
+        let font: String = options.font.chars().filter(|c| !"<>{};\"\\".contains(*c)).collect();
+        Box::new(HtmlFormatter{
+            font,
+            font_size: options.font_size,
+            line_height: options.line_height,
+            background,
+            foreground,
+            color: options.color,
+            run_color: None,
+            line_buff: String::new(),
+        })
+    }
+
+    fn close_run(&mut self){
+        if self.run_color.take().is_some() {
+            self.line_buff.push_str("</span>");
+        }
+    }
+}
+
+impl OutputFormatter for HtmlFormatter{
+    fn begin(&mut self, out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>aRtySt</title>")?;
+        writeln!(out, "<style>")?;
+        let [r, g, b] = self.background;
+        writeln!(out, "body {{ background: #{:02x}{:02x}{:02x}; margin: 0; }}", r, g, b)?;
+        let [r, g, b] = self.foreground;
+        writeln!(out, "pre {{ font-family: \"{}\", monospace; font-size: {}px; color: #{:02x}{:02x}{:02x}; line-height: {}; margin: 0; }}", self.font, self.font_size, r, g, b, self.line_height)?;
+        writeln!(out, "</style>")?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        write!(out, "<pre>")
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        let run = (cell.rgb, cell.background);
+        if self.color != ColorMode::NONE && self.run_color != Some(run) && (!is_blank(&cell.glyph) || cell.background.is_some()) {
+            self.close_run();
+            let [r, g, b] = cell.rgb;
+            self.line_buff.push_str(&format!("<span style=\"color:#{:02x}{:02x}{:02x}", r, g, b));
+            if let Some([r, g, b]) = cell.background {
+                self.line_buff.push_str(&format!(";background-color:#{:02x}{:02x}{:02x}", r, g, b));
+            }
+            self.line_buff.push_str("\">");
+            self.run_color = Some(run);
+        }
+        escape_html(&cell.glyph, &mut self.line_buff);
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.close_run();
+        self.line_buff.push('\n');
+        out.write_all(self.line_buff.as_bytes())?;
+        self.line_buff.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "</pre>")?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")?;
+        out.flush()
+    }
+}
+
+// escapes the characters that have a meaning in html text and double quoted attributes.
+pub fn escape_html(text: &str, output_buff: &mut String){
+    for c in text.chars(){
+        match c {
+            '<' => output_buff.push_str("&lt;"),
+            '>' => output_buff.push_str("&gt;"),
+            '&' => output_buff.push_str("&amp;"),
+            '"' => output_buff.push_str("&quot;"),
+            _ => output_buff.push(c),
+        }
+    }
+}
@@ -1,14 +1,20 @@
 
-use imageproc::image::{GrayImage};
+use std::fs::File;
+use std::io::BufReader;
+use imageproc::image::{GrayImage, RgbImage, DynamicImage, ImageFormat, AnimationDecoder};
+use imageproc::image::io::Reader as ImageReader;
+use imageproc::image::codecs::gif::GifDecoder;
+use imageproc::image::codecs::png::PngDecoder;
+use imageproc::image::imageops::FilterType;
+use imageproc::filter::sharpen_gaussian;
 use crate::segment::SegmentInfo;
 use crate::matrix::Matrix;
-use crate::kernel::*;
 use crate::ditherer::*;
 use crate::{DithType, InterPoints, ThreshOption, meprintln};
 
 ////////// LEGACY ///////////
 
-pub fn generate_matrix_legacy(image: GrayImage, matrix: &mut Matrix<f32>, segment_info: SegmentInfo){
+pub fn generate_matrix_legacy(image: GrayImage, matrix: &mut Matrix<f32>, segment_info: &SegmentInfo){
     for i in 0..segment_info.get_height(){
         for j in 0..segment_info.get_width(){
             let (block_width, block_height) = segment_info.get_block_dims(i, j);
@@ -29,7 +35,109 @@ pub fn take_average(image: &GrayImage, x1: u32, x2: u32, y1: u32, y2: u32) -> f3
         }
     }
     let temp = val / (cnt as f32);
-    return temp / 255.0; // a number between 0 and 1
+    temp / 255.0 // a number between 0 and 1
+}
+
+pub fn generate_color_matrix_legacy(image: RgbImage, matrix: &mut Matrix<[u8; 3]>, segment_info: &SegmentInfo){
+    for i in 0..segment_info.get_height(){
+        for j in 0..segment_info.get_width(){
+            let (block_width, block_height) = segment_info.get_block_dims(i, j);
+            let (x_index, y_index) = segment_info.get_block_start_index(i, j);
+            let value = take_average_rgb(&image, x_index, x_index + block_width, y_index, y_index + block_height);
+            matrix.set(i, j, value).unwrap();
+        }
+    }
+}
+
+pub fn take_average_rgb(image: &RgbImage, x1: u32, x2: u32, y1: u32, y2: u32) -> [u8; 3]{
+    let mut val: [u32; 3] = [0, 0, 0];
+    let mut cnt: u32 = 0;
+    for i in x1..x2{
+        for j in y1..y2{
+            for (acc, c) in val.iter_mut().zip(image.get_pixel(i, j).0){
+                *acc += c as u32;
+            }
+            cnt += 1;
+        }
+    }
+    val.map(|c| (c / cnt.max(1)) as u8)
+}
+
+////////// FRAMES ///////////
+
+// every frame of an animated gif or png with the time it is shown for in
+// seconds. anything else, or any image when `animated` is false, is read as
+// a single frame.
+pub fn load_frames(path: &str, animated: bool) -> Result<Vec<(DynamicImage, f32)>, String> {
+    let reader = ImageReader::open(path).map_err(|e| e.to_string())?
+        .with_guessed_format().map_err(|e| e.to_string())?;
+    let format = reader.format().filter(|_| animated);
+    let frames = match format {
+        Some(ImageFormat::Gif) => {
+            let file = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+            GifDecoder::new(file).map_err(|e| e.to_string())?.into_frames().collect_frames()
+        },
+        Some(ImageFormat::Png) => {
+            let file = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+            let decoder = PngDecoder::new(file).map_err(|e| e.to_string())?;
+            if decoder.is_apng().map_err(|e| e.to_string())? {
+                decoder.apng().map_err(|e| e.to_string())?.into_frames().collect_frames()
+            }else{
+                Ok(Vec::new())
+            }
+        },
+        _ => Ok(Vec::new()),
+    }.map_err(|e| e.to_string())?;
+
+    if frames.is_empty() {
+        let image = reader.decode().map_err(|e| e.to_string())?;
+        return Ok(vec![(image, 0.0)]);
+    }
+    Ok(frames.into_iter().map(|frame| {
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay = numer as f32 / denom.max(1) as f32 / 1000.0;
+        // browsers show gif frames without a delay for 100ms, and so do we
+        let delay = if delay == 0.0 && format == Some(ImageFormat::Gif) { 0.1 } else { delay };
+        (DynamicImage::ImageRgba8(frame.into_buffer()), delay)
+    }).collect())
+}
+
+////////// RESIZE ///////////
+
+// resizes the image to the size of the matrix, blurring it or emphasizing
+// its edges on the way as asked by -G and -S.
+pub fn resize_gray(image: &DynamicImage, width: u32, height: u32, gaussian: f32, sharpen: f32) -> GrayImage{
+    let image = image.grayscale();
+    if gaussian == 0.0 && sharpen == 0.0{
+        image.resize_exact(width, height, FilterType::Gaussian)
+            .into_luma8()
+    }else if gaussian > 0.0 {
+        image.blur(gaussian)
+            .resize_exact(width, height, FilterType::Gaussian)
+            .into_luma8()
+    }else {
+        let stt = image.resize_exact(width, height, FilterType::Gaussian).into_luma8();
+        sharpen_gaussian(&stt, -gaussian, sharpen)
+    }
+}
+
+// same as resize_gray, but keeps the colors. sharpening is done channel by channel.
+pub fn resize_color(image: &DynamicImage, width: u32, height: u32, gaussian: f32, sharpen: f32) -> RgbImage{
+    if gaussian == 0.0 && sharpen == 0.0{
+        image.resize_exact(width, height, FilterType::Gaussian)
+            .into_rgb8()
+    }else if gaussian > 0.0 {
+        image.blur(gaussian)
+            .resize_exact(width, height, FilterType::Gaussian)
+            .into_rgb8()
+    }else {
+        let stt = image.resize_exact(width, height, FilterType::Gaussian).into_rgb8();
+        let channels: Vec<GrayImage> = (0..3).map(|c| {
+            let channel = GrayImage::from_fn(width, height, |x, y| [stt.get_pixel(x, y).0[c]].into());
+            sharpen_gaussian(&channel, -gaussian, sharpen)
+        }).collect();
+        RgbImage::from_fn(width, height, |x, y| [0, 1, 2].map(|c| channels[c].get_pixel(x, y).0[0]).into())
+    }
 }
 
 ////////// KERNEL ///////////
@@ -43,18 +151,25 @@ pub fn generate_matrix(image: GrayImage, matrix: &mut Matrix<f32>){
     }
 }
 
-pub fn apply_transformation(dith_type: &DithType, kernel: Kernel, threshold: ThreshOption,
+pub fn generate_color_matrix(image: RgbImage, matrix: &mut Matrix<[u8; 3]>){
+    for x in 0..image.width(){
+        for y in 0..image.height(){
+            let _ = matrix.set(y, x, image.get_pixel(x, y).0);
+        }
+    }
+}
+
+pub fn apply_transformation(dith_type: &DithType, method: &DitherMethod, threshold: ThreshOption,
                             inter_points: InterPoints, chars_cnt: usize, matrix: &mut Matrix<f32>){
 
     match dith_type {
         DithType::INTER => {
-            match inter_points{
+            let inters = match inter_points{
                 Some(s) => {
                     if chars_cnt > s.len(){
                         meprintln!("WARNING: There are more characters in the char sequence than there are interpolation points specified. This can result in unexpectedly low output quality.");
                     }
-                    let ditherer = InterpolatingKernelDitherer::from(s, kernel.origin, kernel.matrix);
-                    ditherer.dither(matrix);
+                    s
                 },
                 None => {
                     let threshold = match threshold {
@@ -72,9 +187,13 @@ pub fn apply_transformation(dith_type: &DithType, kernel: Kernel, threshold: Thr
                     for i in 1..(chars_cnt-1){
                         inters.push(threshold + (i as f32) * parts); 
                     }
-                    let ditherer = InterpolatingKernelDitherer::from(inters, kernel.origin, kernel.matrix);
-                    ditherer.dither(matrix);
+                    inters
                 }
+            };
+            match method {
+                DitherMethod::KERNEL(kernel, scan) => InterpolatingKernelDitherer::from(inters, kernel.origin, kernel.matrix.clone(), *scan).dither(matrix),
+                DitherMethod::ORDERED(map) => OrderedDitherer::interpolating(inters, map.clone()).dither(matrix),
+                DitherMethod::DOT(classes) => DotDiffusionDitherer::interpolating(inters, classes.clone()).dither(matrix),
             }
         },
         DithType::ONOFF => {
@@ -89,8 +208,11 @@ pub fn apply_transformation(dith_type: &DithType, kernel: Kernel, threshold: Thr
                 }
             };
 
-            let ditherer = OnOffKernelDitherer::from(threshold, kernel.origin, kernel.matrix);
-            ditherer.dither(matrix);
+            match method {
+                DitherMethod::KERNEL(kernel, scan) => OnOffKernelDitherer::from(threshold, kernel.origin, kernel.matrix.clone(), *scan).dither(matrix),
+                DitherMethod::ORDERED(map) => OrderedDitherer::onoff(threshold, map.clone()).dither(matrix),
+                DitherMethod::DOT(classes) => DotDiffusionDitherer::onoff(threshold, classes.clone()).dither(matrix),
+            }
         }
     }
     
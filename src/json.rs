@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::text::Cell;
+use crate::ThreshOption;
+
+// the art and what it was made with, for other programs to read. every row
+// is a string and with --json-cells the glyph index and the gray value of
+// the source image are given for every cell as well.
+pub struct JsonFormatter{
+    out_type: String,
+    chars: Vec<char>,
+    kernel: Option<String>,
+    threshold: ThreshOption,
+    cells: bool,
+    column: usize,
+    columns: usize,
+    height: u32,
+    rows: Vec<String>,
+    cell_rows: Vec<String>,
+    line_buff: String,
+    cell_buff: String,
+}
+
+impl JsonFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(JsonFormatter{
+            out_type: options.out_type.clone(),
+            chars: options.chars.clone(),
+            kernel: options.kernel.clone(),
+            threshold: options.threshold,
+            cells: options.json_cells,
+            column: 0,
+            columns: 0,
+            height: 0,
+            rows: Vec::new(),
+            cell_rows: Vec::new(),
+            line_buff: String::new(),
+            cell_buff: String::new(),
+        })
+    }
+}
+
+impl OutputFormatter for JsonFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, height: u32) -> io::Result<()> {
+        self.height = height;
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.line_buff.push_str(&cell.glyph);
+        self.column += cell.width();
+        if self.cells {
+            if !self.cell_buff.is_empty() {
+                self.cell_buff.push_str(", ");
+            }
+            self.cell_buff.push_str(&format!("{{\"index\": {}, \"gray\": {}}}", cell.index, json_number(cell.gray)));
+        }
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.columns = self.columns.max(self.column);
+        self.column = 0;
+        let mut row = String::new();
+        escape_json(&self.line_buff, &mut row);
+        self.rows.push(row);
+        self.line_buff.clear();
+        if self.cells {
+            self.cell_rows.push(format!("[{}]", self.cell_buff));
+            self.cell_buff.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let (mut out_type, mut chars) = (String::new(), Vec::<String>::new());
+        escape_json(&self.out_type, &mut out_type);
+        for c in &self.chars{
+            let mut temp = String::new();
+            escape_json(&String::from(*c), &mut temp);
+            chars.push(temp);
+        }
+        writeln!(out, "{{")?;
+        // the width is counted in terminal columns, emoji take two
+        writeln!(out, "  \"width\": {},", self.columns)?;
+        writeln!(out, "  \"height\": {},", self.height)?;
+        writeln!(out, "  \"type\": {},", out_type)?;
+        writeln!(out, "  \"chars\": [{}],", chars.join(", "))?;
+        // output types that are not dithered have no kernel and no threshold
+        if let Some(name) = &self.kernel {
+            let mut kernel = String::new();
+            escape_json(name, &mut kernel);
+            writeln!(out, "  \"kernel\": {},", kernel)?;
+        }
+        // nor when nothing was dithered on and off
+        if let Some(threshold) = self.threshold {
+            writeln!(out, "  \"threshold\": {},", json_number(threshold))?;
+        }
+        write!(out, "  \"rows\": [")?;
+        write_list(out, &self.rows)?;
+        if self.cells {
+            write!(out, ",\n  \"cells\": [")?;
+            write_list(out, &self.cell_rows)?;
+        }
+        writeln!(out, "\n}}")?;
+        out.flush()
+    }
+}
+
+fn write_list(out: &mut dyn Write, items: &[String]) -> io::Result<()> {
+    for (i, item) in items.iter().enumerate(){
+        let separator = if i == 0 { "" } else { "," };
+        write!(out, "{}\n    {}", separator, item)?;
+    }
+    if !items.is_empty() {
+        write!(out, "\n  ")?;
+    }
+    write!(out, "]")
+}
+
+// json has no NaN or infinity.
+fn json_number(value: f32) -> String {
+    if value.is_finite() { format!("{}", value) } else { String::from("null") }
+}
+
+// writes `text` as a quoted json string.
+pub fn escape_json(text: &str, output_buff: &mut String){
+    output_buff.push('"');
+    for c in text.chars(){
+        match c {
+            '"' => output_buff.push_str("\\\""),
+            '\\' => output_buff.push_str("\\\\"),
+            '\n' => output_buff.push_str("\\n"),
+            '\r' => output_buff.push_str("\\r"),
+            '\t' => output_buff.push_str("\\t"),
+            c if (c as u32) < 0x20 => output_buff.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => output_buff.push(c),
+        }
+    }
+    output_buff.push('"');
+}
@@ -52,12 +52,263 @@ pub fn get_kernels() -> KerMap {
         origin: (1,0),
     };
 
+    let jjn_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        7.0/48.0,   5.0/48.0,
+            3.0/48.0,   5.0/48.0,   7.0/48.0,   5.0/48.0,   3.0/48.0,
+            1.0/48.0,   3.0/48.0,   5.0/48.0,   3.0/48.0,   1.0/48.0
+        ], 5, 3),
+        origin: (2,0),
+    };
+
+    let burkes_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        8.0/32.0,   4.0/32.0,
+            2.0/32.0,   4.0/32.0,   8.0/32.0,   4.0/32.0,   2.0/32.0
+        ], 5, 2),
+        origin: (2,0),
+    };
+
+    let sierra_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        5.0/32.0,   3.0/32.0,
+            2.0/32.0,   4.0/32.0,   5.0/32.0,   4.0/32.0,   2.0/32.0,
+            0.0,        2.0/32.0,   3.0/32.0,   2.0/32.0,   0.0
+        ], 5, 3),
+        origin: (2,0),
+    };
+
+    let sierra2_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        4.0/16.0,   3.0/16.0,
+            1.0/16.0,   2.0/16.0,   3.0/16.0,   2.0/16.0,   1.0/16.0
+        ], 5, 2),
+        origin: (2,0),
+    };
+
+    let sierralite_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        2.0/4.0,
+            1.0/4.0,    1.0/4.0,    0.0
+        ], 3, 2),
+        origin: (1,0),
+    };
+
+    let shiaufan_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        4.0/8.0,
+            1.0/8.0,    1.0/8.0,    2.0/8.0,    0.0
+        ], 4, 2),
+        origin: (2,0),
+    };
+
+    let shiaufan2_kernel: Kernel = Kernel{
+        matrix: Matrix::<f32>::from(vec![
+            0.0,        0.0,        0.0,        0.0,        8.0/16.0,
+            1.0/16.0,   1.0/16.0,   2.0/16.0,   4.0/16.0,   0.0
+        ], 5, 2),
+        origin: (3,0),
+    };
+
     HashMap::from([
                   ("NONE", none_kernel),
                   ("FS", fs_kernel),
                   ("STUCKI", stucki_kernel),
-                  ("ATKINSON", atkinson_kernel)
+                  ("ATKINSON", atkinson_kernel),
+                  ("JJN", jjn_kernel),
+                  ("BURKES", burkes_kernel),
+                  ("SIERRA", sierra_kernel),
+                  ("SIERRA2", sierra2_kernel),
+                  ("SIERRALITE", sierralite_kernel),
+                  ("SHIAUFAN", shiaufan_kernel),
+                  ("SHIAUFAN2", shiaufan2_kernel)
     ])
 }
 
+impl Kernel{
+    // how much of the error of a pixel is passed on. anything other than 1
+    // loses some of it or makes more of it than there was.
+    pub fn weight_sum(&self) -> f32 {
+        let mut sum = 0.0;
+        for i in 0..self.matrix.get_height(){
+            for j in 0..self.matrix.get_width(){
+                sum += self.matrix.get(i, j).unwrap();
+            }
+        }
+        sum
+    }
+}
+
+// reads a kernel written one row of weights per line, with the weight of the
+// pixel being dithered, which has to be 0, in parentheses. a line starting
+// with `/` gives a number to divide every weight by, and anything after a `#`
+// is a comment. floyd-steinberg would be
+//   0  (0)  7
+//   3   5   1
+//   / 16
+pub fn parse_kernel(text: &str) -> Result<Kernel, String> {
+    let mut rows: Vec<Vec<f32>> = Vec::new();
+    let mut origin: Option<(i32, i32)> = None;
+    let mut divisor: Option<f32> = None;
+    for (n, line) in text.lines().enumerate(){
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('/') {
+            if divisor.is_some() {
+                return Err(format!("line {}: the divisor is given twice", n + 1));
+            }
+            divisor = Some(rest.trim().parse::<f32>().ok().filter(|d| *d != 0.0 && d.is_finite())
+                .ok_or(format!("line {}: {} is not a valid divisor", n + 1, rest.trim()))?);
+            continue;
+        }
+        let mut row = Vec::<f32>::new();
+        for word in line.split_whitespace(){
+            let weight = match word.strip_prefix('(').and_then(|w| w.strip_suffix(')')) {
+                Some(inner) => {
+                    if origin.is_some() {
+                        return Err(format!("line {}: the origin is marked twice", n + 1));
+                    }
+                    origin = Some((row.len() as i32, rows.len() as i32));
+                    inner
+                },
+                None => word,
+            };
+            let weight = weight.parse::<f32>().ok().filter(|w| w.is_finite())
+                .ok_or(format!("line {}: {} is not a valid weight", n + 1, word))?;
+            row.push(weight);
+        }
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(format!("line {}: the row has {} weights but the first one has {}", n + 1, row.len(), first.len()));
+            }
+        }
+        rows.push(row);
+    }
+    let origin = origin.ok_or("the origin is not marked. put the weight of the pixel being dithered in parentheses")?;
+    if rows[origin.1 as usize][origin.0 as usize] != 0.0 {
+        return Err(String::from("the weight at the origin must be 0, the pixel being dithered cannot take its own error"));
+    }
+    let divisor = divisor.unwrap_or(1.0);
+    let (width, height) = (rows[0].len() as u32, rows.len() as u32);
+    let weights = rows.concat().into_iter().map(|w| w / divisor).collect();
+    Ok(Kernel{ matrix: Matrix::<f32>::from(weights, width, height), origin })
+}
+
+// the 8x8 class matrix Knuth gives for dot diffusion
+pub fn knuth_classes() -> Matrix<u32> {
+    Matrix::<u32>::from(vec![
+        34, 48, 40, 32, 29, 15, 23, 31,
+        42, 58, 56, 53, 21,  5,  7, 10,
+        50, 62, 61, 45, 13,  1,  2, 18,
+        38, 46, 54, 37, 25, 17,  9, 26,
+        28, 14, 22, 30, 35, 49, 41, 33,
+        20,  4,  6, 11, 43, 59, 57, 52,
+        12,  0,  3, 19, 51, 63, 60, 44,
+        24, 16,  8, 27, 39, 47, 55, 36
+    ], 8, 8)
+}
+
+// reads a class matrix for dot diffusion, written like a kernel but without
+// the origin and the divisor. the classes are whole numbers, every one of
+// them different, and the pixels are dithered from the lowest class up.
+pub fn parse_classes(text: &str) -> Result<Matrix<u32>, String> {
+    let mut rows: Vec<Vec<u32>> = Vec::new();
+    for (n, line) in text.lines().enumerate(){
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut row = Vec::<u32>::new();
+        for word in line.split_whitespace(){
+            row.push(word.parse::<u32>().map_err(|_| format!("line {}: {} is not a valid class", n + 1, word))?);
+        }
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(format!("line {}: the row has {} classes but the first one has {}", n + 1, row.len(), first.len()));
+            }
+        }
+        rows.push(row);
+    }
+    if rows.is_empty() {
+        return Err(String::from("the file holds no classes"));
+    }
+    let (width, height) = (rows[0].len() as u32, rows.len() as u32);
+    let classes = rows.concat();
+    let mut sorted = classes.clone();
+    sorted.sort();
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!("class {} is given more than once", pair[0]));
+    }
+    Ok(Matrix::<u32>::from(classes, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the message of a parser that was expected to fail
+    fn error<T>(result: Result<T, String>) -> String {
+        match result {
+            Ok(_) => panic!("the text was read without an error"),
+            Err(e) => e,
+        }
+    }
 
+    #[test]
+    fn readme_example_is_the_builtin_fs_kernel() {
+        let parsed = parse_kernel("# floyd-steinberg\n 0  (0)  7\n 3   5   1\n/ 16\n").unwrap();
+        let builtin = get_kernels().remove("FS").unwrap();
+        assert_eq!(parsed.origin, builtin.origin);
+        assert_eq!(parsed.matrix.get_width(), builtin.matrix.get_width());
+        assert_eq!(parsed.matrix.get_height(), builtin.matrix.get_height());
+        for i in 0..builtin.matrix.get_height(){
+            for j in 0..builtin.matrix.get_width(){
+                assert_eq!(parsed.matrix.get(i, j), builtin.matrix.get(i, j));
+            }
+        }
+    }
+
+    #[test]
+    fn kernel_without_divisor_keeps_its_weights() {
+        let kernel = parse_kernel("(0) 0.5\n0.25 0.25").unwrap();
+        assert_eq!(kernel.origin, (0, 0));
+        assert_eq!(kernel.weight_sum(), 1.0);
+    }
+
+    #[test]
+    fn kernel_errors() {
+        assert_eq!(error(parse_kernel("0 0 7\n3 5 1\n/ 16")),
+                   "the origin is not marked. put the weight of the pixel being dithered in parentheses");
+        assert_eq!(error(parse_kernel("")),
+                   "the origin is not marked. put the weight of the pixel being dithered in parentheses");
+        assert_eq!(error(parse_kernel("(0) 7\n(0) 1")), "line 2: the origin is marked twice");
+        assert_eq!(error(parse_kernel("0 (1) 7\n3 5 1")),
+                   "the weight at the origin must be 0, the pixel being dithered cannot take its own error");
+        assert_eq!(error(parse_kernel("0 (0) 7\n3 5")), "line 2: the row has 2 weights but the first one has 3");
+        assert_eq!(error(parse_kernel("(0) 7\n/ sixteen")), "line 2: sixteen is not a valid divisor");
+        assert_eq!(error(parse_kernel("(0) 7\n/ 0")), "line 2: 0 is not a valid divisor");
+        assert_eq!(error(parse_kernel("(0) 7\n/ 16\n/ 8")), "line 3: the divisor is given twice");
+        assert_eq!(error(parse_kernel("(0) seven")), "line 1: seven is not a valid weight");
+        assert_eq!(error(parse_kernel("(zero) 7")), "line 1: (zero) is not a valid weight");
+    }
+
+    #[test]
+    fn classes_are_read_row_by_row() {
+        let classes = parse_classes("# bayer\n0 2\n3 1 # last row\n").unwrap();
+        assert_eq!((classes.get_width(), classes.get_height()), (2, 2));
+        assert_eq!(classes.get(0, 1), Ok(2));
+        assert_eq!(classes.get(1, 0), Ok(3));
+    }
+
+    #[test]
+    fn classes_errors() {
+        assert_eq!(error(parse_classes("0 1\n2 1")), "class 1 is given more than once");
+        assert_eq!(error(parse_classes("0 1\n2 -3")), "line 2: -3 is not a valid class");
+        assert_eq!(error(parse_classes("0 1.5")), "line 1: 1.5 is not a valid class");
+        assert_eq!(error(parse_classes("0 1 2\n3 4")), "line 2: the row has 2 classes but the first one has 3");
+        assert_eq!(error(parse_classes("")), "the file holds no classes");
+        assert_eq!(error(parse_classes("# only a comment\n\n")), "the file holds no classes");
+    }
+}
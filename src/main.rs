@@ -4,10 +4,7 @@ use getopts::{Options, Occur, HasArg};
 use std::path::Path;
 use std::fs::File;
 use std::io::Read;
-use imageproc::image::io::Reader as ImageReader;
-use imageproc::image::imageops::FilterType;
 use imageproc::image::DynamicImage;
-use imageproc::filter::sharpen_gaussian;
 
 mod segment;
 mod image_process;
@@ -15,19 +12,78 @@ mod matrix;
 mod text;
 mod ditherer;
 mod kernel;
+mod template;
+mod formatter;
+mod html;
+mod svg;
+mod png;
+mod font;
+mod json;
+mod asciicast;
+mod ans;
+mod encoding;
+mod petscii;
+mod c64;
+mod bitmap;
+mod source;
+mod chat;
+mod ramp;
+mod printer;
+mod proportional;
+mod emoji;
+mod ansi;
+mod palette;
+mod bluenoise;
 
 use crate::segment::*;
 use crate::image_process::*;
 use crate::matrix::Matrix;
 use crate::text::*;
 use crate::kernel::*;
+use crate::ditherer::{DitherMethod, ScanOrder, ThresholdMap};
+use crate::bluenoise::{blue_noise, load_mask};
+use crate::template::Template;
+use crate::formatter::*;
+use crate::ansi::detect_color_mode;
+use crate::ramp::{RampLevel, Stack, auto_ramp, parse_ramp, ramp_points, overstrike_ramp};
+use crate::proportional::{GlyphMetrics, SAMPLES_PER_EM, load_metrics};
+use crate::font::BitmapFont;
+use crate::encoding::{Encoding, parse_cp437_chars};
+use crate::petscii::{SCREEN_WIDTH, SCREEN_HEIGHT, CELL_SIZE};
 
-const PROGDESC: &'static str = "A simple program that converts images into ascii art.\n";
+const PROGDESC: &str = "A simple program that converts images into ascii art.\n";
 
 #[derive(PartialEq)]
 pub enum ProgType{
     TXT,
     BRAILE,
+    HALFBLOCK,
+    QUADRANT,
+    SEXTANT,
+    OCTANT,
+    PETSCII,
+    PIXEL,
+    OVERSTRIKE,
+    PROPORTIONAL,
+    EMOJI,
+}
+
+impl ProgType{
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgType::TXT => "TXT",
+            ProgType::BRAILE => "BRAILE",
+            ProgType::HALFBLOCK => "HALFBLOCK",
+            ProgType::QUADRANT => "QUADRANT",
+            ProgType::SEXTANT => "SEXTANT",
+            ProgType::OCTANT => "OCTANT",
+            ProgType::PETSCII => "PETSCII",
+            ProgType::PIXEL => "PIXEL",
+            ProgType::OVERSTRIKE => "OVERSTRIKE",
+            ProgType::PROPORTIONAL => "PROPORTIONAL",
+            ProgType::EMOJI => "EMOJI",
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -36,17 +92,51 @@ pub enum SegType{
     LEGACY,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum DithType{
     ONOFF,
     INTER,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColorMode{
+    NONE,
+    TRUECOLOR,
+    ANSI256,
+    ANSI16,
+}
+
 pub type ThreshOption = Option<f32>;
 pub type CharsOption = Option<String>;
 pub type OutputFile = Option<File>;
 pub type InterPoints = Option<Vec<f32>>;
 
+pub struct ProgArgs{
+    out_type: ProgType,
+    seg_type: SegType,
+    dith_type: DithType,
+    kernel: Kernel,
+    threshold: ThreshOption,
+    out_format: String,
+    fmt_opts: FormatOptions,
+    contrast: f32,
+    brighten: i32,
+    width: u32,
+    height: u32,
+    output: OutputFile,
+    chars: CharsOption,
+    inter_points: InterPoints,
+    screen: Option<ThresholdMap>,
+    classes: Option<Matrix<u32>>,
+    scan: ScanOrder,
+    ramp: Option<Vec<RampLevel>>,
+    stacks: Vec<Stack>,
+    metrics: Vec<GlyphMetrics>,
+    gaussian: f32,
+    sharpen: f32,
+    input: String,
+}
+
 #[macro_export]
 macro_rules! meprintln {
     ($($x:tt)*) => {
@@ -58,52 +148,60 @@ macro_rules! meprintln {
 
 fn print_help(progname: String, parser: Options){
     println!("HDS aRtySt v{}\nGNU GPL 3.0 license.\n\n", env!("CARGO_PKG_VERSION"));
-    println!("{}\n\n{}\n  NOTE: character and line formatting are not implemented yet.\n  NOTE: HTML output format is not implemented yet.", parser.short_usage(&progname), parser.usage(PROGDESC));
+    println!("{}\n\n{}", parser.short_usage(&progname), parser.usage(PROGDESC));
+    println!("\nFORMATSTR placeholders:\n  -f: {{}} or {{c}} character, {{v}} gray value (0-255), {{i}} row, {{j}} column,\n      {{r}} {{g}} {{b}} average color (0-255), {{hex}} average color as rrggbb\n  -F: {{}} or {{l}} the formatted line, {{i}} row\n  use {{{{ and }}}} for literal braces. \\n \\t \\r \\e and \\\\ are also recognized.");
 }
 
-fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
-                                            ProgType,
-                                            SegType,
-                                            DithType,
-                                            String,
-                                            ThreshOption,
-                                            String, 
-                                            String,
-                                            f32,
-                                            i32,
-                                            u32,
-                                            u32,
-                                            OutputFile,
-                                            CharsOption,
-                                            InterPoints,
-                                            f32,
-                                            f32,
-                                            String), ()>{
+fn parse_args(args: Vec<String>, map_kernel: &KerMap, map_formatter: &FmtMap) -> Result<ProgArgs, ()>{
 
     let ker_types: Vec<&str> = map_kernel.clone().into_keys().collect();
-    let help_kernel_types = ker_types.join("|");
+    let help_kernel_types = ker_types.join("|") + "|@FILENAME";
+    let fmt_types: Vec<&str> = map_formatter.clone().into_keys().collect();
+    let help_fmt_types = fmt_types.join("|");
 
     let progname = args[0].clone();
     let mut parser = Options::new();
     parser.optflag("h", "help", "display this help message");
-    parser.opt("t", "type", "type of output", "TXT|BRAILE", HasArg::Yes, Occur::Optional);
+    parser.opt("t", "type", "type of output", "TXT|BRAILE|HALFBLOCK|QUADRANT|SEXTANT|OCTANT|PETSCII|PIXEL|OVERSTRIKE|PROPORTIONAL|EMOJI", HasArg::Yes, Occur::Optional);
     parser.opt("s", "seg-type", "how to segmentate the image", "RESIZE|LEGACY", HasArg::Yes, Occur::Optional);
     parser.opt("d", "dith-type", "type of the ditherer used", "ONOFF|INTERPOLATING", HasArg::Yes, Occur::Optional);
     parser.opt("k", "kernel", "type of kernel to use in ditherer", help_kernel_types.as_str(), HasArg::Yes, Occur::Optional);
+    parser.opt("", "ordered", "dither by comparing the pixels with a threshold map instead of spreading the error with a kernel. ORDER of the bayer matrix 1-6 default=3, SIZE of the halftone cells 2-16 default=4, SEED of the noise default=0, SIZE of the blue noise mask 4-128 default=64 or a grayscale image to use as the mask", "BAYER[:ORDER]|CLUSTER[:SIZE]|NOISE[:SEED]|BLUE[:SIZE|:@FILENAME]", HasArg::Yes, Occur::Optional);
+    parser.opt("", "dot-diffusion", "dither class by class with the dot diffusion of Knuth instead of spreading the error with a kernel. the class matrix is the one of Knuth or is read from a file. see README", "KNUTH|@FILENAME", HasArg::Yes, Occur::Optional);
+    parser.opt("", "scan", "order the kernel ditherers go through the pixels in. SERPENTINE reverses every other row and COLUMN goes down the columns. default=RASTER", "RASTER|SERPENTINE|COLUMN", HasArg::Yes, Occur::Optional);
+    parser.opt("", "mask-cache", "file the blue noise mask of --ordered BLUE is saved to, and read from when it is already there", "FILENAME", HasArg::Yes, Occur::Optional);
     parser.opt("T", "threshold", "cut-off threshold", "FLOAT", HasArg::Yes, Occur::Optional);
-    parser.opt("f", "fmt", "format string for each character", "FORMATSTR", HasArg::Yes, Occur::Optional);
-    parser.opt("F", "fmtln", "format string for each line", "FORMATSTR", HasArg::Yes, Occur::Optional);
+    parser.opt("O", "out-format", "format of the output", help_fmt_types.as_str(), HasArg::Yes, Occur::Optional);
+    parser.opt("", "color", "color the output after the image. TEXT output uses ansi escape sequences, AUTO picks what the terminal supports. MIRC output keeps to the 16 basic colors with 16 and uses all 99 otherwise. default=NONE", "NONE|TRUECOLOR|256|16|AUTO", HasArg::Yes, Occur::Optional);
+    parser.opt("", "font", "font family used by the HTML and SVG outputs and font name recorded by the ANS output. default=monospace", "FONT", HasArg::Yes, Occur::Optional);
+    parser.opt("", "font-size", "font size in pixels used by the HTML, SVG and PNG outputs. default=12", "FLOAT", HasArg::Yes, Occur::Optional);
+    parser.opt("", "line-height", "line height relative to the font size used by the HTML and SVG outputs and the PROPORTIONAL output type. default=1.0", "FLOAT", HasArg::Yes, Occur::Optional);
+    parser.opt("", "bitmap-font", "BDF or PSF font used by the PNG output instead of the built-in one", "FILENAME", HasArg::Yes, Occur::Optional);
+    parser.opt("", "background", "background color used by the HTML, SVG and PNG outputs. default=#000000", "COLOR", HasArg::Yes, Occur::Optional);
+    parser.opt("", "foreground", "text color used by the HTML, SVG and PNG outputs. default=#ffffff", "COLOR", HasArg::Yes, Occur::Optional);
+    parser.opt("", "encoding", "character encoding of the TEXT output. default=UTF8", "UTF8|CP437", HasArg::Yes, Occur::Optional);
+    parser.opt("", "title", "title of the art, recorded by the ANS output and naming the variables of the XBM, C and RUST outputs", "STRING", HasArg::Yes, Occur::Optional);
+    parser.opt("", "author", "author of the art, recorded by the ANS output", "STRING", HasArg::Yes, Occur::Optional);
+    parser.opt("", "max-length", "longest message in bytes of the MIRC and BBCODE outputs. longer output is split into several messages. default=400 for MIRC and 60000 for BBCODE", "INTEGER", HasArg::Yes, Occur::Optional);
+    parser.opt("", "metrics", "TTF or OTF font, or metrics file, giving the widths of the characters for the PROPORTIONAL output type. see README", "FILENAME", HasArg::Yes, Occur::Optional);
+    parser.opt("", "strikes", "most characters printed on top of each other by the OVERSTRIKE output type. default=3", "INTEGER", HasArg::Yes, Occur::Optional);
+    parser.optflag("", "backspace", "overstrike with backspaces instead of carriage returns in PRINTER output");
+    parser.optflag("", "plain", "write PBM output in the plain (P1) format instead of the raw (P4) one");
+    parser.optflag("", "json-cells", "also give the glyph index and gray value of every cell in JSON output");
+    parser.opt("f", "fmt", "format string for each character. see below for placeholders", "FORMATSTR", HasArg::Yes, Occur::Optional);
+    parser.opt("F", "fmtln", "format string for each line. see below for placeholders", "FORMATSTR", HasArg::Yes, Occur::Optional);
     parser.opt("c", "contrast", "contrast level", "FLOAT", HasArg::Yes, Occur::Optional);
     parser.opt("b", "brighten", "increase image brightness level", "INTEGER", HasArg::Yes, Occur::Optional);
     parser.opt("W", "width", "width of the output character matrix", "INTEGER", HasArg::Yes, Occur::Optional);
     parser.opt("H", "height", "width of the output character matrix", "INTEGER", HasArg::Yes, Occur::Optional);
     parser.opt("o", "output", "output file default=stdout", "FILENAME", HasArg::Yes, Occur::Optional);
-    parser.opt("C", "chars", "list of characters to use as output", "STRING|@FILENAME", HasArg::Yes, Occur::Optional);
+    parser.opt("C", "chars", "list of characters to use as output. CP437 is a code page 437 shading ramp and CP437:XX,XX,.. picks code page 437 characters by their hex codes", "STRING|@FILENAME", HasArg::Yes, Occur::Optional);
+    parser.opt("", "attr-ramp", "print TXT output with the bold and faint attributes as extra brightness levels. AUTO combines the characters of -C with every attribute, or the ramp is read from a file. see README", "AUTO|@FILENAME", HasArg::Yes, Occur::Optional);
     parser.opt("I", "inter-points", "interpolation points", "(FLOAT,)*|@FILENAME", HasArg::Yes, Occur::Optional);
     parser.opt("G", "gaussian", "apply a gaussian filter.", "FLOAT", HasArg::Yes, Occur::Optional);
     parser.opt("S", "sharpen", "use sharpen to emphasize on the edges on the image. best used along with -G. for this option to have any effect, -G value must be negative.", "FLOAT>0", HasArg::Yes, Occur::Optional);
 
-    let matches = match parser.parse(args[1..].into_iter()) {
+    let matches = match parser.parse(args[1..].iter()) {
         Ok(s) => s,
         Err(e)=>{
             meprintln!("Error: {}", e);
@@ -115,9 +213,25 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
     let mut seg_type: SegType = SegType::LEGACY;
     let mut dith_type: DithType = DithType::INTER;
     let mut ker_type: String = String::from("NONE"); 
+    let mut kernel: Kernel = map_kernel.get("NONE").expect("the NONE kernel is always there").to_owned();
+    let mut screen: Option<ThresholdMap> = None;
+    let mut screen_name: Option<String> = None;
+    let mut scan: ScanOrder = ScanOrder::RASTER;
+    let mut classes: Option<Matrix<u32>> = None;
+    let mut out_format: String = String::from("TEXT");
+    let mut color: ColorMode = ColorMode::NONE;
+    let mut font: String = String::from("monospace");
+    let mut font_size: f32 = 12.0;
+    let mut line_height: f32 = 1.0;
+    let mut background: String = String::from("#000000");
+    let mut foreground: String = String::from("#ffffff");
+    let mut bitmap_font: Option<BitmapFont> = None;
+    let mut encoding: Encoding = Encoding::UTF8;
+    let mut max_length: Option<usize> = None;
+    let mut strikes: usize = 3;
     let mut threshold: ThreshOption = None;
-    let /* mut */ fmt_str: String = String::from("{}");
-    let /* mut */ fmt_ln_str: String = String::from("{}\n");
+    let mut fmt: Template = Template::parse_cell("{}").expect("default format string is valid");
+    let mut fmt_ln: Template = Template::parse_line("{}\n").expect("default format string is valid");
     let mut contrast: f32 = 0.0;
     let mut brighten: i32 = 0;
     let mut width: u32 = 0;
@@ -127,6 +241,7 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
     let mut output: OutputFile = None;
     let mut chars: CharsOption = None;
     let mut inter_points: InterPoints = None;
+    let mut ramp: Option<Vec<RampLevel>> = None;
 
 
     if matches.opt_present("h"){
@@ -138,14 +253,23 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
         let temp: String = match matches.opt_str("t"){
             Some(s) => s,
             None => {
-                meprintln!("-t option expects an argument: TXT|BRAILE");
+                meprintln!("-t option expects an argument: TXT|BRAILE|HALFBLOCK|QUADRANT|SEXTANT|OCTANT|PETSCII|PIXEL|OVERSTRIKE|PROPORTIONAL|EMOJI");
                 return Err(());
             }
         }.trim().to_lowercase();
         if temp == "txt" { out_type = ProgType::TXT; }
         else if temp == "braile" { out_type = ProgType::BRAILE; }
+        else if temp == "halfblock" { out_type = ProgType::HALFBLOCK; }
+        else if temp == "quadrant" { out_type = ProgType::QUADRANT; }
+        else if temp == "sextant" { out_type = ProgType::SEXTANT; }
+        else if temp == "octant" { out_type = ProgType::OCTANT; }
+        else if temp == "petscii" { out_type = ProgType::PETSCII; }
+        else if temp == "pixel" { out_type = ProgType::PIXEL; }
+        else if temp == "overstrike" { out_type = ProgType::OVERSTRIKE; }
+        else if temp == "proportional" { out_type = ProgType::PROPORTIONAL; }
+        else if temp == "emoji" { out_type = ProgType::EMOJI; }
         else {
-            meprintln!("-t option expects an argument: TXT|BRAILE");
+            meprintln!("-t option expects an argument: TXT|BRAILE|HALFBLOCK|QUADRANT|SEXTANT|OCTANT|PETSCII|PIXEL|OVERSTRIKE|PROPORTIONAL|EMOJI");
             return Err(());
         }
     }
@@ -161,8 +285,8 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
         }.trim().to_lowercase();
         if temp == "onoff" { dith_type = DithType::ONOFF; }
         else if temp == "inter" || temp == "interpolating" { 
-            if out_type == ProgType::BRAILE{
-                meprintln!("Illegal Combination of options: cannot use interpolating ditherer for braile output.");
+            if out_type != ProgType::TXT && out_type != ProgType::OVERSTRIKE {
+                meprintln!("Illegal Combination of options: cannot use interpolating ditherer with any output type other than TXT and OVERSTRIKE.");
                 return Err(());
             }
             dith_type = DithType::INTER; 
@@ -180,15 +304,187 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
                 meprintln!("-k option expects an argument: {}", help_kernel_types);
                 return Err(());
             }
-        }.trim().to_lowercase();
-        if map_kernel.contains_key(temp.to_uppercase().as_str()) {
+        }.trim().to_string();
+        if let Some(path) = temp.strip_prefix('@') {
+            let temp_path = Path::new(path);
+            if !temp_path.is_file() {
+                meprintln!("cannot open {} for reading: File does not exist or is not a regular file.", path);
+                return Err(());
+            }
+            let mut string = String::new();
+            File::options().read(true).open(temp_path).expect("unexpected error occured when openning kernel file")
+                .read_to_string(&mut string).unwrap();
+            kernel = match parse_kernel(&string) {
+                Ok(s) => s,
+                Err(e) => {
+                    meprintln!("error while reading the kernel given to -k: {}", e);
+                    return Err(());
+                }
+            };
+            let sum = kernel.weight_sum();
+            if (sum - 1.0).abs() > 0.001 {
+                meprintln!("WARNING: the weights of the kernel in {} add up to {}. less than 1 loses some of the error and more than 1 exaggerates it.", path, sum);
+            }else{
+                meprintln!("the weights of the kernel in {} add up to {}.", path, sum);
+            }
+            ker_type = temp;
+        }else if map_kernel.contains_key(temp.to_uppercase().as_str()) {
             ker_type = temp.to_uppercase();
+            kernel = map_kernel.get(ker_type.as_str()).unwrap().to_owned();
         }else{
             meprintln!("-k option expects an argument: {}", help_kernel_types);
             return Err(());
         }
     }
 
+    if matches.opt_present("ordered"){
+        // checked first, a blue noise mask takes a while to make
+        if matches.opt_present("k") {
+            meprintln!("Illegal Combination of options: an ordered ditherer does not spread the error. cannot use --ordered along with -k.");
+            return Err(());
+        }
+        let temp: String = match matches.opt_str("ordered"){
+            Some(s) => s,
+            None => {
+                meprintln!("--ordered option expects an argument: BAYER[:ORDER]|CLUSTER[:SIZE]|NOISE[:SEED]|BLUE[:SIZE|:@FILENAME]");
+                return Err(());
+            }
+        }.trim().to_uppercase();
+        let (name, param) = match temp.split_once(':') {
+            Some((name, param)) => (name, Some(param)),
+            None => (temp.as_str(), None),
+        };
+        // the map is named in the JSON output where the kernel would be
+        screen = match (name, param) {
+            ("BAYER", _) => match param.unwrap_or("3").parse::<u32>() {
+                Ok(order) if (1..=6).contains(&order) => {
+                    screen_name = Some(format!("BAYER:{}", order));
+                    Some(ThresholdMap::bayer(order))
+                },
+                _ => {
+                    meprintln!("the order of the bayer matrix given to --ordered must be an INTEGER between 1 and 6.");
+                    return Err(());
+                }
+            },
+            ("CLUSTER", _) => match param.unwrap_or("4").parse::<u32>() {
+                Ok(size) if (2..=16).contains(&size) => {
+                    screen_name = Some(format!("CLUSTER:{}", size));
+                    Some(ThresholdMap::clustered(size))
+                },
+                _ => {
+                    meprintln!("the size of the halftone cells given to --ordered must be an INTEGER between 2 and 16.");
+                    return Err(());
+                }
+            },
+            ("NOISE", _) => match param.unwrap_or("0").parse::<u64>() {
+                Ok(seed) => {
+                    screen_name = Some(format!("NOISE:{}", seed));
+                    Some(ThresholdMap::NOISE(seed))
+                },
+                _ => {
+                    meprintln!("the seed given to --ordered must be a non-negative INTEGER.");
+                    return Err(());
+                }
+            },
+            ("BLUE", Some(param)) if param.starts_with('@') => {
+                // the name is upper cased with the rest of the argument
+                let path = matches.opt_str("ordered").unwrap_or_default();
+                let path = &path.trim()[path.trim().find('@').unwrap_or(0) + 1..];
+                match load_mask(path) {
+                    Ok(map) => {
+                        screen_name = Some(String::from("BLUE"));
+                        Some(map)
+                    },
+                    Err(e) => {
+                        meprintln!("error while reading the mask given to --ordered: {}", e);
+                        return Err(());
+                    }
+                }
+            },
+            ("BLUE", _) => match param.unwrap_or("64").parse::<u32>() {
+                Ok(size) if (4..=128).contains(&size) => {
+                    screen_name = Some(format!("BLUE:{}", size));
+                    match blue_noise(size, matches.opt_str("mask-cache").as_deref()) {
+                        Ok(map) => Some(map),
+                        Err(e) => {
+                            meprintln!("error while reading the mask given to --mask-cache: {}", e);
+                            return Err(());
+                        }
+                    }
+                },
+                _ => {
+                    meprintln!("the size of the blue noise mask given to --ordered must be an INTEGER between 4 and 128.");
+                    return Err(());
+                }
+            },
+            _ => {
+                meprintln!("--ordered option expects an argument: BAYER[:ORDER]|CLUSTER[:SIZE]|NOISE[:SEED]|BLUE[:SIZE|:@FILENAME]");
+                return Err(());
+            }
+        };
+    }
+    if matches.opt_present("mask-cache") && !screen_name.as_deref().is_some_and(|n| n.starts_with("BLUE:")) {
+        meprintln!("WARNING: --mask-cache is only used when --ordered makes a BLUE mask.");
+    }
+
+    if matches.opt_present("dot-diffusion"){
+        let temp: String = match matches.opt_str("dot-diffusion"){
+            Some(s) => s,
+            None => {
+                meprintln!("--dot-diffusion option expects an argument: KNUTH|@FILENAME");
+                return Err(());
+            }
+        }.trim().to_string();
+        if let Some(path) = temp.strip_prefix('@') {
+            let temp_path = Path::new(path);
+            if !temp_path.is_file() {
+                meprintln!("cannot open {} for reading: File does not exist or is not a regular file.", path);
+                return Err(());
+            }
+            let mut string = String::new();
+            File::options().read(true).open(temp_path).expect("unexpected error occured when openning class matrix file")
+                .read_to_string(&mut string).unwrap();
+            classes = match parse_classes(&string) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    meprintln!("error while reading the class matrix given to --dot-diffusion: {}", e);
+                    return Err(());
+                }
+            };
+            screen_name = Some(format!("DOT:{}", temp));
+        }else if temp.to_uppercase() == "KNUTH" {
+            classes = Some(knuth_classes());
+            screen_name = Some(String::from("DOT:KNUTH"));
+        }else{
+            meprintln!("--dot-diffusion option expects an argument: KNUTH|@FILENAME");
+            return Err(());
+        }
+        if matches.opt_present("k") || screen.is_some() {
+            meprintln!("Illegal Combination of options: dot diffusion spreads the error on its own. cannot use --dot-diffusion along with -k or --ordered.");
+            return Err(());
+        }
+    }
+
+    if matches.opt_present("scan"){
+        let temp: String = match matches.opt_str("scan"){
+            Some(s) => s,
+            None => {
+                meprintln!("--scan option expects an argument: RASTER|SERPENTINE|COLUMN");
+                return Err(());
+            }
+        }.trim().to_lowercase();
+        if temp == "raster" { scan = ScanOrder::RASTER; }
+        else if temp == "serpentine" { scan = ScanOrder::SERPENTINE; }
+        else if temp == "column" { scan = ScanOrder::COLUMN; }
+        else {
+            meprintln!("--scan option expects an argument: RASTER|SERPENTINE|COLUMN");
+            return Err(());
+        }
+        if screen.is_some() || classes.is_some() {
+            meprintln!("WARNING: only the kernel ditherers go through the pixels in rows or columns. --scan is ignored.");
+        }
+    }
+
     if matches.opt_present("T"){
         let temp = match matches.opt_str("T"){
             Some(s) => s,
@@ -207,11 +503,176 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
         };
     }
 
+    if matches.opt_present("O"){
+        let temp: String = match matches.opt_str("O"){
+            Some(s) => s,
+            None => {
+                meprintln!("-O option expects an argument: {}", help_fmt_types);
+                return Err(());
+            }
+        }.trim().to_uppercase();
+        if map_formatter.contains_key(temp.as_str()) {
+            out_format = temp;
+        }else{
+            meprintln!("-O option expects an argument: {}", help_fmt_types);
+            return Err(());
+        }
+    }
+
+    if matches.opt_present("color"){
+        let temp: String = match matches.opt_str("color"){
+            Some(s) => s,
+            None => {
+                meprintln!("--color option expects an argument: NONE|TRUECOLOR|256|16|AUTO");
+                return Err(());
+            }
+        }.trim().to_lowercase();
+        if temp == "none" { color = ColorMode::NONE; }
+        else if temp == "truecolor" || temp == "24bit" { color = ColorMode::TRUECOLOR; }
+        else if temp == "256" { color = ColorMode::ANSI256; }
+        else if temp == "16" { color = ColorMode::ANSI16; }
+        else if temp == "auto" { color = detect_color_mode(); }
+        else {
+            meprintln!("--color option expects an argument: NONE|TRUECOLOR|256|16|AUTO");
+            return Err(());
+        }
+    }
+
+    if matches.opt_present("encoding"){
+        let temp: String = match matches.opt_str("encoding"){
+            Some(s) => s,
+            None => {
+                meprintln!("--encoding option expects an argument: UTF8|CP437");
+                return Err(());
+            }
+        }.trim().to_lowercase();
+        if temp == "utf8" || temp == "utf-8" { encoding = Encoding::UTF8; }
+        else if temp == "cp437" { encoding = Encoding::CP437; }
+        else {
+            meprintln!("--encoding option expects an argument: UTF8|CP437");
+            return Err(());
+        }
+    }
+
+    if let Some(s) = matches.opt_str("font"){
+        font = s;
+    }
+    if matches.opt_present("font-size"){
+        let temp = match matches.opt_str("font-size"){
+            Some(s) => s,
+            None => {
+                meprintln!("--font-size option expects an argument: FLOAT");
+                return Err(());
+            }
+        }.parse::<f32>();
+
+        font_size = match temp {
+            Ok(s) if s > 0.0 => s,
+            _ => {
+                meprintln!("the argument given to --font-size is not a valid positive FLOAT number.");
+                return Err(());
+            }
+        };
+    }
+    if matches.opt_present("max-length"){
+        let temp = match matches.opt_str("max-length"){
+            Some(s) => s,
+            None => {
+                meprintln!("--max-length option expects an argument: INTEGER");
+                return Err(());
+            }
+        }.parse::<usize>();
+
+        max_length = match temp {
+            Ok(s) if s > 0 => Some(s),
+            _ => {
+                meprintln!("the argument given to --max-length is not a valid positive INTEGER.");
+                return Err(());
+            }
+        };
+    }
+    if matches.opt_present("strikes"){
+        let temp = match matches.opt_str("strikes"){
+            Some(s) => s,
+            None => {
+                meprintln!("--strikes option expects an argument: INTEGER");
+                return Err(());
+            }
+        }.parse::<usize>();
+
+        // deeper stacks are slow to search and rarely add a darkness
+        strikes = match temp {
+            Ok(s) if (1..=4).contains(&s) => s,
+            _ => {
+                meprintln!("the argument given to --strikes must be an INTEGER between 1 and 4.");
+                return Err(());
+            }
+        };
+    }
+    if matches.opt_present("line-height"){
+        let temp = match matches.opt_str("line-height"){
+            Some(s) => s,
+            None => {
+                meprintln!("--line-height option expects an argument: FLOAT");
+                return Err(());
+            }
+        }.parse::<f32>();
+
+        line_height = match temp {
+            Ok(s) if s > 0.0 => s,
+            _ => {
+                meprintln!("the argument given to --line-height is not a valid positive FLOAT number.");
+                return Err(());
+            }
+        };
+    }
+    if let Some(s) = matches.opt_str("bitmap-font"){
+        bitmap_font = match BitmapFont::load(&s){
+            Ok(f) => Some(f),
+            Err(e) => {
+                meprintln!("error while loading the font given to --bitmap-font: {}", e);
+                return Err(());
+            }
+        };
+    }
+    if let Some(s) = matches.opt_str("background"){
+        background = s;
+    }
+    if let Some(s) = matches.opt_str("foreground"){
+        foreground = s;
+    }
+
     if matches.opt_present("f"){
-        meprintln!("Formatting features are not implemented yet. Ignoring this option: -f");
+        let temp: String = match matches.opt_str("f"){
+            Some(s) => s,
+            None => {
+                meprintln!("-f option expects an argument: FORMATSTR");
+                return Err(());
+            }
+        };
+        fmt = match Template::parse_cell(&temp){
+            Ok(s) => s,
+            Err(e) => {
+                meprintln!("error while parsing the argument given to -f: {}", e);
+                return Err(());
+            }
+        };
     }
     if matches.opt_present("F"){
-        meprintln!("Formatting features are not implemented yet. Ignoring this option: -F");
+        let temp: String = match matches.opt_str("F"){
+            Some(s) => s,
+            None => {
+                meprintln!("-F option expects an argument: FORMATSTR");
+                return Err(());
+            }
+        };
+        fmt_ln = match Template::parse_line(&temp){
+            Ok(s) => s,
+            Err(e) => {
+                meprintln!("error while parsing the argument given to -F: {}", e);
+                return Err(());
+            }
+        };
     }
 
     if matches.opt_present("c"){
@@ -299,7 +760,25 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
             meprintln!("cannot open {} for writing. File exists and is not a regular file.", temp);
             return Err(());
         }
-        output = Some(File::options().write(true).append(false).create(true).open(temp).expect("unexpected error occured when openning output file"));
+        // without -O the format is guessed from the extension of the file
+        if !matches.opt_present("O") {
+            if let Some(extension) = temp_path.extension().and_then(|e| e.to_str()) {
+                let extension = match extension.to_uppercase().as_str() {
+                    "CAST" => String::from("ASCIICAST"),
+                    "HTM" => String::from("HTML"),
+                    "H" => String::from("C"),
+                    "RS" => String::from("RUST"),
+                    "SH" => String::from("SHELL"),
+                    "IRC" => String::from("MIRC"),
+                    "BBC" => String::from("BBCODE"),
+                    e => String::from(e),
+                };
+                if map_formatter.contains_key(extension.as_str()) {
+                    out_format = extension;
+                }
+            }
+        }
+        output = Some(File::options().write(true).append(false).truncate(true).create(true).open(temp).expect("unexpected error occured when openning output file"));
     }
 
     if matches.opt_present("C"){
@@ -311,7 +790,7 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
             }
         };
         // file specified
-        if temp.chars().nth(0).unwrap() == '@' {
+        if temp.starts_with('@') {
             let temp_path = Path::new(&temp);
             if !temp_path.exists() {
                 meprintln!("cannot open {} for reading: File does not exist.", temp);
@@ -326,7 +805,14 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
             file.read_to_string(&mut string).unwrap();
             chars = Some(string);
         }else{ // string specified
-            chars = Some(temp);
+            chars = match parse_cp437_chars(&temp) {
+                Ok(Some(s)) => Some(s),
+                Ok(None) => Some(temp),
+                Err(e) => {
+                    meprintln!("-C option: {}", e);
+                    return Err(());
+                }
+            };
         }
     }
 
@@ -342,7 +828,7 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
             meprintln!("Illegal Combination of options: cannot specify interpolation points with OnOff ditherer.");
             return Err(());
         }
-        if temp.chars().nth(0).unwrap() == '@' {
+        if temp.starts_with('@') {
             let temp_path = Path::new(&temp);
             if !temp_path.exists() {
                 meprintln!("cannot open {} for reading: File does not exist.", temp);
@@ -368,7 +854,7 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
                         meprintln!("Illegal Argument: the list of numbers provided to -I must be in increasing order");
                         return Err(());
                     }
-                    if s < 0.0 || s > 1.0 {
+                    if !(0.0..=1.0).contains(&s) {
                         meprintln!("Illegal argument: the list on numbers provided to -I must contain only numbers between 0 and 1");
                         return Err(());
                     }
@@ -433,8 +919,8 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
         }.trim().to_lowercase();
         if temp == "resize" { seg_type = SegType::RESIZE; }
         else if temp == "legacy" { 
-            if out_type == ProgType::BRAILE{
-                meprintln!("Illegal Combination of options: cannot use Legacy segmentation for braile output.");
+            if ![ProgType::TXT, ProgType::PETSCII, ProgType::OVERSTRIKE, ProgType::EMOJI].contains(&out_type) {
+                meprintln!("Illegal Combination of options: cannot use Legacy segmentation with any output type other than TXT, PETSCII, OVERSTRIKE and EMOJI.");
                 return Err(());
             }
             if gaussian != 0.0 {
@@ -454,7 +940,123 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
     }
 
     // check for illegal combinations
-    
+    if out_format == "C64" && out_type != ProgType::PETSCII {
+        meprintln!("Illegal Combination of options: C64 output can only be used with PETSCII output type.");
+        return Err(());
+    }
+    // the bitmap fonts have no emoji
+    if out_format == "PNG" && out_type == ProgType::EMOJI {
+        meprintln!("Illegal Combination of options: PNG output cannot draw EMOJI output type.");
+        return Err(());
+    }
+    // 1-bit images are made of the dithered pixels themselves
+    if out_format == "PBM" || out_format == "XBM" {
+        if matches.opt_present("t") && out_type != ProgType::PIXEL {
+            meprintln!("Illegal Combination of options: {} output can only be used with PIXEL output type.", out_format);
+            return Err(());
+        }
+        out_type = ProgType::PIXEL;
+    }
+    // line printer art needs the stacks, which only OVERSTRIKE makes
+    if out_format == "PRINTER" && out_type != ProgType::OVERSTRIKE {
+        if matches.opt_present("t") {
+            meprintln!("Illegal Combination of options: PRINTER output can only be used with OVERSTRIKE output type.");
+            return Err(());
+        }
+        out_type = ProgType::OVERSTRIKE;
+    }
+    if out_type == ProgType::OVERSTRIKE && !matches.opt_present("O") && out_format == "TEXT" {
+        out_format = String::from("PRINTER");
+    }
+    if out_type == ProgType::PETSCII {
+        if (width != 0 && width != SCREEN_WIDTH) || (height != 0 && height != SCREEN_HEIGHT) {
+            meprintln!("WARNING: PETSCII output always fills a {}x{} screen. the given width and height are ignored.", SCREEN_WIDTH, SCREEN_HEIGHT);
+        }
+        width = SCREEN_WIDTH;
+        height = SCREEN_HEIGHT;
+    }
+
+    if matches.opt_present("attr-ramp"){
+        let temp: String = match matches.opt_str("attr-ramp"){
+            Some(s) => s,
+            None => {
+                meprintln!("--attr-ramp option expects an argument: AUTO|@FILENAME");
+                return Err(());
+            }
+        };
+        if out_type != ProgType::TXT || dith_type != DithType::INTER {
+            meprintln!("Illegal Combination of options: --attr-ramp can only be used with TXT output type and the INTERPOLATING ditherer.");
+            return Err(());
+        }
+        if inter_points.is_some() {
+            meprintln!("Illegal Combination of options: the interpolation points of --attr-ramp come from the brightness of its levels and cannot be given with -I.");
+            return Err(());
+        }
+        // brightness is measured in the font the art will be looked at with
+        let measure_font = bitmap_font.clone().unwrap_or_else(BitmapFont::builtin);
+        if temp.to_uppercase() == "AUTO" {
+            ramp = Some(auto_ramp(&get_char_array(&chars), &measure_font));
+        }else if let Some(path) = temp.strip_prefix('@') {
+            if matches.opt_present("C") {
+                meprintln!("Illegal Combination of options: the characters of a ramp file cannot be changed with -C.");
+                return Err(());
+            }
+            let temp_path = Path::new(path);
+            if !temp_path.is_file() {
+                meprintln!("cannot open {} for reading: File does not exist or is not a regular file.", path);
+                return Err(());
+            }
+            let mut string = String::new();
+            File::options().read(true).open(temp_path).expect("unexpected error occured when openning ramp file")
+                .read_to_string(&mut string).unwrap();
+            ramp = match parse_ramp(&string, &measure_font) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    meprintln!("error while reading the ramp given to --attr-ramp: {}", e);
+                    return Err(());
+                }
+            };
+        }else{
+            meprintln!("--attr-ramp option expects an argument: AUTO|@FILENAME");
+            return Err(());
+        }
+        inter_points = ramp.as_ref().map(|r| ramp_points(&r.iter().map(|l| l.brightness).collect::<Vec<f32>>()));
+    }
+
+    let mut stacks: Vec<Stack> = Vec::new();
+    if out_type == ProgType::OVERSTRIKE {
+        stacks = overstrike_ramp(&get_char_array(&chars), strikes, &bitmap_font.clone().unwrap_or_else(BitmapFont::builtin));
+        if stacks.len() < 2 {
+            meprintln!("the characters given to -C cannot make more than one darkness.");
+            return Err(());
+        }
+        if inter_points.is_none() {
+            inter_points = Some(ramp_points(&stacks.iter().map(|s| s.brightness).collect::<Vec<f32>>()));
+        }
+    }
+
+    let mut metrics: Vec<GlyphMetrics> = Vec::new();
+    if out_type == ProgType::PROPORTIONAL {
+        let path = match matches.opt_str("metrics") {
+            Some(s) => s,
+            None => {
+                meprintln!("PROPORTIONAL output type needs the widths of the characters. give a font or a metrics file with --metrics.");
+                return Err(());
+            }
+        };
+        metrics = match load_metrics(&path, &get_char_array(&chars), line_height) {
+            Ok(s) => s,
+            Err(e) => {
+                meprintln!("error while reading the metrics given to --metrics: {}", e);
+                return Err(());
+            }
+        };
+        if ["k", "d", "ordered", "dot-diffusion"].iter().any(|o| matches.opt_present(o)) {
+            meprintln!("WARNING: PROPORTIONAL output type carries the error along the row on its own. -k, -d, --ordered and --dot-diffusion are ignored.");
+        }
+    }else if matches.opt_present("metrics") {
+        meprintln!("WARNING: --metrics is only used by PROPORTIONAL output type.");
+    }
 
     if matches.free.len() != 1{
         meprintln!("You need to specify the input image file name");
@@ -471,156 +1073,317 @@ fn parse_args(args: Vec<String>, map_kernel: &KerMap) -> Result<(
     }
     let input = matches.free[0].clone();
 
-    Ok((out_type, seg_type, dith_type, ker_type, threshold, fmt_str, fmt_ln_str, contrast, brighten, width, height, output, chars, inter_points, gaussian, sharpen, input))
+    // PETSCII, PROPORTIONAL and EMOJI pick their glyphs without a ditherer,
+    // and colored HALFBLOCK output is not dithered at all
+    let dithered = !([ProgType::PETSCII, ProgType::PROPORTIONAL, ProgType::EMOJI].contains(&out_type)
+        || (out_type == ProgType::HALFBLOCK && color != ColorMode::NONE));
+    // the threshold the ONOFF ditherer ends up using. only TXT output can be interpolated
+    let used_threshold = if !dithered {
+        None
+    }else if dith_type == DithType::ONOFF || (out_type != ProgType::TXT && out_type != ProgType::OVERSTRIKE) {
+        Some(threshold.unwrap_or(0.5))
+    }else{
+        None
+    };
+    let fmt_opts = FormatOptions{
+        fmt, fmt_ln, color, font, font_size, line_height, background, foreground, bitmap_font,
+        json_cells: matches.opt_present("json-cells"),
+        encoding,
+        title: matches.opt_str("title").unwrap_or_default(),
+        author: matches.opt_str("author").unwrap_or_default(),
+        plain: matches.opt_present("plain"),
+        max_length,
+        out_type: String::from(out_type.name()),
+        chars: match &ramp {
+            Some(levels) => levels.iter().map(|l| l.glyph).collect(),
+            None if out_type == ProgType::OVERSTRIKE => stacks.iter().map(|s| s.glyphs.last().copied().unwrap_or(' ')).collect(),
+            None if out_type == ProgType::PROPORTIONAL => metrics.iter().map(|m| m.glyph).collect(),
+            None => get_glyph_table(&out_type, &get_char_array(&chars)),
+        },
+        stacks: stacks.clone(),
+        backspace: matches.opt_present("backspace"),
+        kernel: dithered.then(|| screen_name.unwrap_or_else(|| ker_type.clone())),
+        threshold: used_threshold,
+    };
+
+    Ok(ProgArgs{ out_type, seg_type, dith_type, kernel, threshold, out_format, fmt_opts, contrast, brighten, width, height, output, chars, inter_points, screen, classes, scan, ramp, stacks, metrics, gaussian, sharpen, input })
 }
 
 fn main() {
 
     let args: Vec<String> = std::env::args().collect();
     let map_kernel = get_kernels();
-    let parsed = parse_args(args, &map_kernel);
+    let map_formatter = get_formatters();
+    let parsed = parse_args(args, &map_kernel, &map_formatter);
     if parsed.is_err() {
             println!("An Error occured when parsing input arguments.");
             println!("See --help for more info");
             println!("Aborting...");
             return;
     }
-    let (
-        out_type,
-        seg_type,
-        dith_type,
-        ker_type,
-        threshold,
-        fmt_str,
-        fmt_ln_str,
-        contrast,
-        brighten,
-        mut width,
-        mut height,
-        output,
-        chars,
-        inter_points,
-        gaussian,
-        sharpen,
-        input,
-        ) = parsed.expect("All error cases have been checked");
-
-    if width == 0 && height == 0 {
+    let mut args = parsed.expect("All error cases have been checked");
+
+    if args.width == 0 && args.height == 0 {
         meprintln!("Input error: atleast one of --width or --height options must be specified.");
         eprintln!("see --help for more info.");
         eprintln!("Aborting...");
         return;
     }
 
-    let dyn_image = ImageReader::open(&input).expect("Unexpected error while reading input file").decode().unwrap();
+    let formatter = map_formatter.get(args.out_format.as_str()).unwrap()(&args.fmt_opts);
 
-    if width == 0 {
-        let aspect_ratio = (dyn_image.width() as f32) / (dyn_image.height() as f32);
-        width = ((height as f32) * aspect_ratio).floor() as u32;
-    }
+    // animated formatters get every frame of the input, the others only the first one
+    let images = match load_frames(&args.input, formatter.animated()) {
+        Ok(images) => images,
+        Err(e) => {
+            meprintln!("Error while reading {}: {}", args.input, e);
+            return;
+        }
+    };
+    let first = &images[0].0;
 
-    if height == 0 {
-        let iaspect_ratio = (dyn_image.height() as f32) / (dyn_image.width() as f32);
-        height = ((width as f32) * iaspect_ratio).floor() as u32;
+    // the width of PROPORTIONAL output is counted in em, and its lines can be higher than that
+    let line_height = if args.out_type == ProgType::PROPORTIONAL { args.fmt_opts.line_height } else { 1.0 };
+    if args.width == 0 {
+        let aspect_ratio = (first.width() as f32) / (first.height() as f32);
+        args.width = ((args.height as f32) * aspect_ratio * line_height).floor() as u32;
     }
 
-    let width = width;
-    let height = height;
+    if args.height == 0 {
+        let iaspect_ratio = (first.height() as f32) / (first.width() as f32);
+        args.height = ((args.width as f32) * iaspect_ratio / line_height).floor() as u32;
+    }
 
-    let kernel = map_kernel.get(ker_type.as_str()).unwrap().to_owned();
+    let method = match (&args.screen, &args.classes) {
+        (Some(map), _) => DitherMethod::ORDERED(map.clone()),
+        (None, Some(classes)) => DitherMethod::DOT(classes.clone()),
+        (None, None) => DitherMethod::KERNEL(args.kernel.clone(), args.scan),
+    };
 
-    match out_type{
-        ProgType::TXT => {
-            produce_txt(width, height, dyn_image, seg_type, contrast, brighten, chars, fmt_str, fmt_ln_str, dith_type, kernel, threshold, inter_points, gaussian, sharpen, output);
-        },
-        ProgType::BRAILE => {
-            produce_braile(width, height, dyn_image, contrast, brighten, chars, fmt_str, fmt_ln_str, kernel, threshold, gaussian, sharpen, output);
+    let mut frames = Vec::<Frame>::with_capacity(images.len());
+    for (image, delay) in &images{
+        let mut rows = match args.out_type{
+            ProgType::TXT | ProgType::OVERSTRIKE => {
+                produce_txt(&args, image, &method)
+            },
+            ProgType::BRAILE => {
+                produce_braile(&args, image, &method)
+            },
+            ProgType::HALFBLOCK => {
+                produce_halfblock(&args, image, &method)
+            },
+            ProgType::QUADRANT | ProgType::SEXTANT | ProgType::OCTANT => {
+                produce_blocks(&args, image, &method)
+            },
+            ProgType::PETSCII => {
+                produce_petscii(&args, image)
+            },
+            ProgType::PIXEL => {
+                produce_pixel(&args, image, &method)
+            },
+            ProgType::PROPORTIONAL => {
+                produce_proportional(&args, image)
+            },
+            ProgType::EMOJI => {
+                produce_emoji(&args, image)
+            }
+        };
+        if !formatter.own_palette() {
+            quantize_rows(&mut rows, args.fmt_opts.color);
         }
+        frames.push(Frame{ rows, delay: *delay });
     }
+    print_output(frames, formatter, args.output);
 }
 
-fn produce_txt(width: u32, height: u32, dyn_image: DynamicImage, seg_type: SegType,
-                  contrast:f32, brighten: i32, chars: CharsOption, fmt_str: String,
-                  fmt_ln_str: String, dith_type: DithType, kernel: Kernel,
-                  threshold: ThreshOption, inter_points: InterPoints, gaussian: f32, 
-                  sharpen: f32, output: OutputFile){
+fn produce_txt(args: &ProgArgs, dyn_image: &DynamicImage, method: &DitherMethod) -> Vec<Vec<Cell>> {
+    let (width, height) = (args.width, args.height);
 
     let mut matrix = Matrix::<f32>::new(width, height, 0.0);
-    match seg_type {
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+    match args.seg_type {
         SegType::RESIZE => {
-            let dyn_image = dyn_image
-                .brighten(brighten)
-                .adjust_contrast(contrast)
-                .grayscale();
-
-            let stt_image = if gaussian == 0.0 && sharpen == 0.0{
-                dyn_image .resize_exact(width, height, FilterType::Gaussian)
-                    .into_luma8()
-            }else if gaussian > 0.0 {
-                dyn_image.blur(gaussian)
-                    .resize_exact(width, height, FilterType::Gaussian)
-                    .into_luma8()
-            }else {
-                let stt = dyn_image.resize_exact(width, height, FilterType::Gaussian).into_luma8();
-                sharpen_gaussian(&stt, -gaussian, sharpen)
-            };
-
-            generate_matrix(stt_image, &mut matrix);
+            generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+            generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
         },
         SegType::LEGACY => {
+            let segment_info = SegmentInfo::generate(dyn_image.width(), dyn_image.height(), width, height);
+
+            generate_color_matrix_legacy(dyn_image.to_rgb8(), &mut colors, &segment_info);
+
             let stt_image = dyn_image
-                .brighten(brighten)
-                .adjust_contrast(contrast)
                 .grayscale()
                 .into_luma8();
 
-            let segment_info = SegmentInfo::generate(stt_image.width(), stt_image.height(), width, height);
-
-            generate_matrix_legacy(stt_image, &mut matrix, segment_info);
+            generate_matrix_legacy(stt_image, &mut matrix, &segment_info);
         }
     }
-    let len = match chars {
-        Some(ref s) => s.len(),
-        None => DEFAULT_CHARS_LEN,
-    };
-    apply_transformation(&dith_type, kernel, threshold, inter_points, len, &mut matrix);
-    print_output(matrix, fmt_str, fmt_ln_str, chars, ProgType::TXT, dith_type, output);
+    let source = matrix.clone();
+    if args.out_type == ProgType::OVERSTRIKE {
+        apply_transformation(&args.dith_type, method, args.threshold, args.inter_points.clone(), args.stacks.len(), &mut matrix);
+        return build_rows_overstrike(matrix, source, colors, &args.stacks, args.dith_type);
+    }
+    if let Some(ramp) = &args.ramp {
+        apply_transformation(&args.dith_type, method, args.threshold, args.inter_points.clone(), ramp.len(), &mut matrix);
+        return build_rows_ramp(matrix, source, colors, ramp);
+    }
+    // counted in characters, the ramp need not be ascii
+    let len = get_char_array(&args.chars).len();
+    apply_transformation(&args.dith_type, method, args.threshold, args.inter_points.clone(), len, &mut matrix);
+    build_rows(matrix, source, colors, &args.chars, &ProgType::TXT, args.dith_type, args.fmt_opts.color)
 }
 
-fn produce_braile(width: u32, height: u32, dyn_image: DynamicImage, contrast: f32,
-                  brighten: i32, chars: CharsOption, fmt_str: String, fmt_ln_str: String,
-                  kernel: Kernel, threshold: ThreshOption, gaussian:f32,
-                  sharpen:f32, output: OutputFile){
-    let width = if width % 2 == 0 {width} else {width + 1};
-    let height = match height % 4 {
-        0 => height,
-        1 => height + 3,
-        2 => height - 2,
-        3 => height + 1,
+fn produce_braile(args: &ProgArgs, dyn_image: &DynamicImage, method: &DitherMethod) -> Vec<Vec<Cell>> {
+    let width = if args.width.is_multiple_of(2) {args.width} else {args.width + 1};
+    let height = match args.height % 4 {
+        0 => args.height,
+        1 => args.height + 3,
+        2 => args.height - 2,
+        3 => args.height + 1,
         _ => unreachable!()
     };
 
     let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
 
     let dyn_image = dyn_image
-        .brighten(brighten)
-        .adjust_contrast(contrast)
-        .grayscale();
-
-    let stt_image = if gaussian == 0.0 && sharpen == 0.0{
-        dyn_image .resize_exact(width, height, FilterType::Gaussian)
-            .into_luma8()
-    }else if gaussian > 0.0 {
-        dyn_image.blur(gaussian)
-            .resize_exact(width, height, FilterType::Gaussian)
-            .into_luma8()
-    }else {
-        let stt = dyn_image.resize_exact(width, height, FilterType::Gaussian).into_luma8();
-        sharpen_gaussian(&stt, -gaussian, sharpen)
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+    generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    let source = matrix.clone();
+    apply_transformation(&DithType::ONOFF, method, args.threshold, InterPoints::None, 2 /* not used but must be 2 to avoid unwanted warning */, &mut matrix);
+    build_rows(matrix, source, colors, &args.chars, &ProgType::BRAILE, DithType::ONOFF, args.fmt_opts.color)
+}
+
+fn produce_halfblock(args: &ProgArgs, dyn_image: &DynamicImage, method: &DitherMethod) -> Vec<Vec<Cell>> {
+    let width = args.width;
+    let height = if args.height.is_multiple_of(2) {args.height} else {args.height + 1};
+
+    let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+    generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    let source = matrix.clone();
+    // with colors, both pixels of a cell are drawn in their own color and there is nothing to dither
+    if args.fmt_opts.color == ColorMode::NONE {
+        apply_transformation(&DithType::ONOFF, method, args.threshold, InterPoints::None, 2, &mut matrix);
+    }
+    build_rows(matrix, source, colors, &args.chars, &ProgType::HALFBLOCK, DithType::ONOFF, args.fmt_opts.color)
+}
+
+fn produce_blocks(args: &ProgArgs, dyn_image: &DynamicImage, method: &DitherMethod) -> Vec<Vec<Cell>> {
+    // every character holds a 2x2, 2x3 or 2x4 grid of pixels
+    let (cell_width, cell_height) = match args.out_type {
+        ProgType::QUADRANT => (2, 2),
+        ProgType::SEXTANT => (2, 3),
+        ProgType::OCTANT => (2, 4),
+        _ => unreachable!()
     };
+    let width = args.width.max(1).next_multiple_of(cell_width);
+    let height = args.height.max(1).next_multiple_of(cell_height);
+
+    let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+    generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    let source = matrix.clone();
+    apply_transformation(&DithType::ONOFF, method, args.threshold, InterPoints::None, 2, &mut matrix);
+    build_rows(matrix, source, colors, &args.chars, &args.out_type, DithType::ONOFF, args.fmt_opts.color)
+}
+
+fn produce_pixel(args: &ProgArgs, dyn_image: &DynamicImage, method: &DitherMethod) -> Vec<Vec<Cell>> {
+    let (width, height) = (args.width.max(1), args.height.max(1));
+
+    let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+    generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    let source = matrix.clone();
+    apply_transformation(&DithType::ONOFF, method, args.threshold, InterPoints::None, 2, &mut matrix);
+    build_rows(matrix, source, colors, &args.chars, &ProgType::PIXEL, DithType::ONOFF, args.fmt_opts.color)
+}
+
+fn produce_proportional(args: &ProgArgs, dyn_image: &DynamicImage) -> Vec<Vec<Cell>> {
+    // every row is sampled finely enough to tell the glyphs apart by their widths
+    let (width, height) = (args.width.max(1) * SAMPLES_PER_EM, args.height.max(1));
 
-    generate_matrix(stt_image, &mut matrix);
-    apply_transformation(&DithType::ONOFF, kernel, threshold, InterPoints::None, 2 /* not used but must be 2 to avoid unwanted warning */, &mut matrix);
-    print_output(matrix, fmt_str, fmt_ln_str, chars, ProgType::BRAILE, DithType::ONOFF, output);
+    let mut source = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+    generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut source);
+    build_rows_proportional(source, colors, &args.metrics, args.width.max(1) as f32)
+}
+
+fn produce_emoji(args: &ProgArgs, dyn_image: &DynamicImage) -> Vec<Vec<Cell>> {
+    let (width, height) = (args.width.max(1), args.height.max(1));
+
+    let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    // legacy segmentation averages the blocks of the image, which only works if there are enough pixels to average
+    if args.seg_type == SegType::LEGACY && dyn_image.width() >= width && dyn_image.height() >= height {
+        let segment_info = SegmentInfo::generate(dyn_image.width(), dyn_image.height(), width, height);
+        generate_color_matrix_legacy(dyn_image.to_rgb8(), &mut colors, &segment_info);
+        generate_matrix_legacy(dyn_image.grayscale().into_luma8(), &mut matrix, &segment_info);
+    }else{
+        generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+        generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    }
+    // the emoji are matched against the colors, there is nothing to dither
+    let source = matrix.clone();
+    build_rows(matrix, source, colors, &args.chars, &ProgType::EMOJI, DithType::ONOFF, args.fmt_opts.color)
 }
 
+fn produce_petscii(args: &ProgArgs, dyn_image: &DynamicImage) -> Vec<Vec<Cell>> {
+    // every character of the screen is 8x8 pixels
+    let (width, height) = (SCREEN_WIDTH * CELL_SIZE, SCREEN_HEIGHT * CELL_SIZE);
+
+    let mut matrix = Matrix::<f32>::new(width, height, 0.0);
+    let mut colors = Matrix::<[u8; 3]>::new(width, height, [0, 0, 0]);
+
+    let dyn_image = dyn_image
+        .brighten(args.brighten)
+        .adjust_contrast(args.contrast);
+
+    // legacy segmentation averages the blocks of the image, which only works if there are enough pixels to average
+    if args.seg_type == SegType::LEGACY && dyn_image.width() >= width && dyn_image.height() >= height {
+        let segment_info = SegmentInfo::generate(dyn_image.width(), dyn_image.height(), width, height);
+        generate_color_matrix_legacy(dyn_image.to_rgb8(), &mut colors, &segment_info);
+        generate_matrix_legacy(dyn_image.grayscale().into_luma8(), &mut matrix, &segment_info);
+    }else{
+        generate_color_matrix(resize_color(&dyn_image, width, height, args.gaussian, args.sharpen), &mut colors);
+        generate_matrix(resize_gray(&dyn_image, width, height, args.gaussian, args.sharpen), &mut matrix);
+    }
+    // the glyphs are matched against the colors, there is nothing to dither
+    let source = matrix.clone();
+    build_rows(matrix, source, colors, &args.chars, &ProgType::PETSCII, DithType::ONOFF, args.fmt_opts.color)
+}
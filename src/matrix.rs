@@ -10,8 +10,8 @@ impl<T: std::marker::Copy> Matrix<T>{
     pub fn new(width: u32, height: u32, filler: T) -> Matrix<T> {
         Matrix{
             storage: vec![filler; (width * height).try_into().unwrap()],
-            width:width,
-            height:height,
+            width,
+            height,
         }
     }
 
@@ -21,8 +21,8 @@ impl<T: std::marker::Copy> Matrix<T>{
         }
         Matrix{
             storage: input,
-            width: width,
-            height: height,
+            width,
+            height,
         }
     }
 
@@ -0,0 +1,121 @@
+use crate::ColorMode;
+
+// a fixed set of colors. `offset` is the number of the first color in the
+// terminal's (or whatever else's) own numbering of the palette.
+#[derive(Clone)]
+pub struct Palette{
+    colors: Vec<[u8; 3]>,
+    lab: Vec<[f32; 3]>,
+    offset: usize,
+}
+
+impl Palette{
+    pub fn from(colors: Vec<[u8; 3]>, offset: usize) -> Palette {
+        let lab = colors.iter().map(|c| rgb_to_lab(*c)).collect();
+        Palette{ colors, lab, offset }
+    }
+
+    // the basic 16 colors with their xterm default values.
+    pub fn ansi16() -> Palette {
+        Palette::from(vec![
+            [0x00, 0x00, 0x00], [0xcd, 0x00, 0x00], [0x00, 0xcd, 0x00], [0xcd, 0xcd, 0x00],
+            [0x00, 0x00, 0xee], [0xcd, 0x00, 0xcd], [0x00, 0xcd, 0xcd], [0xe5, 0xe5, 0xe5],
+            [0x7f, 0x7f, 0x7f], [0xff, 0x00, 0x00], [0x00, 0xff, 0x00], [0xff, 0xff, 0x00],
+            [0x5c, 0x5c, 0xff], [0xff, 0x00, 0xff], [0x00, 0xff, 0xff], [0xff, 0xff, 0xff],
+        ], 0)
+    }
+
+    // the 6x6x6 color cube and the gray ramp of xterm-256 (colors 16 to 255).
+    // the first 16 colors are left out since most terminal themes redefine them.
+    pub fn xterm256() -> Palette {
+        let levels: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        let mut colors = Vec::<[u8; 3]>::with_capacity(240);
+        for r in levels{
+            for g in levels{
+                for b in levels{
+                    colors.push([r, g, b]);
+                }
+            }
+        }
+        for i in 0..24u8{
+            let v = 8 + 10 * i;
+            colors.push([v, v, v]);
+        }
+        Palette::from(colors, 16)
+    }
+
+    pub fn for_mode(color: ColorMode) -> Option<Palette> {
+        match color {
+            ColorMode::ANSI256 => Some(Palette::xterm256()),
+            ColorMode::ANSI16 => Some(Palette::ansi16()),
+            _ => None,
+        }
+    }
+
+    // index (in the palette's own numbering) of the color perceptually closest to `rgb`.
+    pub fn nearest(&self, rgb: [u8; 3]) -> usize {
+        let target = rgb_to_lab(rgb);
+        let mut best = 0;
+        let mut best_dist = f32::MAX;
+        for (i, lab) in self.lab.iter().enumerate(){
+            let dist = lab_distance(*lab, target);
+            if dist < best_dist {
+                best = i;
+                best_dist = dist;
+            }
+        }
+        best + self.offset
+    }
+
+    pub fn get(&self, index: usize) -> [u8; 3] {
+        self.colors[index - self.offset]
+    }
+
+    pub fn quantize(&self, rgb: [u8; 3]) -> [u8; 3] {
+        self.get(self.nearest(rgb))
+    }
+}
+
+// CIE L*a*b* under D65, the space in which color differences are compared.
+pub fn rgb_to_lab(rgb: [u8; 3]) -> [f32; 3] {
+    let lin = rgb.map(|c| {
+        let c = (c as f32) / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    });
+    let x = (0.4124 * lin[0] + 0.3576 * lin[1] + 0.1805 * lin[2]) / 0.95047;
+    let y = 0.2126 * lin[0] + 0.7152 * lin[1] + 0.0722 * lin[2];
+    let z = (0.0193 * lin[0] + 0.1192 * lin[1] + 0.9505 * lin[2]) / 1.08883;
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// squared CIE76 color difference.
+pub fn lab_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+// reads a color written as #rgb or #rrggbb.
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            let mut rgb = [0u8; 3];
+            for (k, c) in hex.chars().enumerate(){
+                rgb[k] = (c.to_digit(16)? * 17) as u8;
+            }
+            Some(rgb)
+        },
+        6 => {
+            let mut rgb = [0u8; 3];
+            for (k, channel) in rgb.iter_mut().enumerate(){
+                *channel = u8::from_str_radix(&hex[2*k..2*k + 2], 16).ok()?;
+            }
+            Some(rgb)
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+use crate::palette::{Palette, rgb_to_lab, lab_distance};
+
+// the colors of the c64 (pepto's palette), in the order of their numbers.
+pub const C64_COLORS: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00], [0xff, 0xff, 0xff], [0x68, 0x37, 0x2b], [0x70, 0xa4, 0xb2],
+    [0x6f, 0x3d, 0x86], [0x58, 0x8d, 0x43], [0x35, 0x28, 0x79], [0xb8, 0xc7, 0x6f],
+    [0x6f, 0x4f, 0x25], [0x43, 0x39, 0x00], [0x9a, 0x67, 0x59], [0x44, 0x44, 0x44],
+    [0x6c, 0x6c, 0x6c], [0x9a, 0xd2, 0x84], [0x6c, 0x5e, 0xb5], [0x95, 0x95, 0x95],
+];
+
+pub const SCREEN_WIDTH: u32 = 40;
+pub const SCREEN_HEIGHT: u32 = 25;
+pub const CELL_SIZE: u32 = 8;
+
+// the block graphics of the petscii character set the art is made of: the
+// screen code, its unicode counterpart, the unicode counterpart of its
+// reversed version (screen code + 128) if there is one, and the 8x8 bitmap
+// with the leftmost pixel in the highest bit.
+const GLYPHS: [(u8, char, Option<char>, [u8; 8]); 23] = [
+    (32, ' ', Some('█'), [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    (97, '▌', Some('▐'), [0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0]),
+    (98, '▄', Some('▀'), [0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]),
+    (99, '▔', Some('▇'), [0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    (100, '▁', Some('\u{1FB86}'), [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff]),
+    (101, '▏', Some('\u{1FB8B}'), [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]),
+    (102, '▒', Some('▒'), [0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55]),
+    (103, '▕', Some('▉'), [0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01]),
+    (104, '\u{1FB8F}', None, [0x00, 0x00, 0x00, 0x00, 0xaa, 0x55, 0xaa, 0x55]),
+    (106, '\u{1FB87}', Some('▊'), [0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03]),
+    (108, '▗', Some('▛'), [0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x0f, 0x0f]),
+    (111, '▂', Some('\u{1FB85}'), [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff]),
+    (116, '▎', Some('\u{1FB8A}'), [0xc0, 0xc0, 0xc0, 0xc0, 0xc0, 0xc0, 0xc0, 0xc0]),
+    (117, '▍', Some('\u{1FB89}'), [0xe0, 0xe0, 0xe0, 0xe0, 0xe0, 0xe0, 0xe0, 0xe0]),
+    (118, '\u{1FB88}', Some('▋'), [0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07, 0x07]),
+    (119, '\u{1FB82}', Some('▆'), [0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    (120, '\u{1FB83}', Some('▅'), [0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    (121, '▃', Some('\u{1FB84}'), [0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff]),
+    (123, '▖', Some('▜'), [0x00, 0x00, 0x00, 0x00, 0xf0, 0xf0, 0xf0, 0xf0]),
+    (124, '▝', Some('▙'), [0x0f, 0x0f, 0x0f, 0x0f, 0x00, 0x00, 0x00, 0x00]),
+    (126, '▘', Some('▟'), [0xf0, 0xf0, 0xf0, 0xf0, 0x00, 0x00, 0x00, 0x00]),
+    (127, '▚', Some('▞'), [0xf0, 0xf0, 0xf0, 0xf0, 0x0f, 0x0f, 0x0f, 0x0f]),
+    (105, '◤', Some('◢'), [0xff, 0xfe, 0xfc, 0xf8, 0xf0, 0xe0, 0xc0, 0x80]),
+];
+
+pub fn c64_palette() -> Palette {
+    Palette::from(C64_COLORS.to_vec(), 0)
+}
+
+// the unicode counterpart of a screen code. letters, digits and punctuation
+// are not used by the art but are there for completeness.
+pub fn petscii_char(code: u8) -> char {
+    for (normal, c, reversed, _) in GLYPHS{
+        if code == normal {
+            return c;
+        }
+        if code == normal | 0x80 {
+            if let Some(r) = reversed {
+                return r;
+            }
+        }
+    }
+    match code {
+        0 => '@',
+        1..=26 => (b'A' + code - 1) as char,
+        27 => '[',
+        28 => '£',
+        29 => ']',
+        30 => '↑',
+        31 => '←',
+        32..=63 => code as char,
+        _ => '?',
+    }
+}
+
+// every glyph the art can be made of, reversed ones included, as
+// (screen code, bitmap of 64 pixels in rows).
+pub fn glyph_bitmaps() -> Vec<(u8, u64)> {
+    let mut bitmaps = Vec::<(u8, u64)>::new();
+    for (code, _, reversed, rows) in GLYPHS{
+        let bitmap = rows.iter().fold(0u64, |acc, row| (acc << 8) | *row as u64);
+        bitmaps.push((code, bitmap));
+        if reversed.is_some() {
+            bitmaps.push((code | 0x80, !bitmap));
+        }
+    }
+    bitmaps
+}
+
+// picks the glyph and the foreground color that reproduce the 64 pixels of
+// a cell best on the given background. returns (screen code, color).
+pub fn match_cell(pixels: &[[f32; 3]], background: usize, bitmaps: &[(u8, u64)], palette_lab: &[[f32; 3]]) -> (u8, usize) {
+    // the distance of every pixel to every color of the palette
+    let distances: Vec<[f32; 16]> = pixels.iter().map(|lab| {
+        let mut row = [0.0f32; 16];
+        for (d, color) in row.iter_mut().zip(palette_lab){
+            *d = lab_distance(*lab, *color);
+        }
+        row
+    }).collect();
+
+    let mut best = (32u8, background);
+    let mut best_error = f32::MAX;
+    for (code, bitmap) in bitmaps{
+        for color in 0..16{
+            let mut error = 0.0;
+            for (p, row) in distances.iter().enumerate(){
+                let on = (bitmap >> (63 - p)) & 1 == 1;
+                error += if on { row[color] } else { row[background] };
+            }
+            if error < best_error {
+                best_error = error;
+                best = (*code, color);
+            }
+        }
+    }
+    best
+}
+
+// the color of the screen: the palette color most pixels are closest to.
+pub fn pick_background(pixels: &[[f32; 3]], palette_lab: &[[f32; 3]]) -> usize {
+    let mut counts = [0u32; 16];
+    for lab in pixels{
+        let mut nearest = 0;
+        for (i, color) in palette_lab.iter().enumerate(){
+            if lab_distance(*lab, *color) < lab_distance(*lab, palette_lab[nearest]) {
+                nearest = i;
+            }
+        }
+        counts[nearest] += 1;
+    }
+    (0..16).max_by_key(|i| counts[*i]).unwrap()
+}
+
+pub fn palette_lab() -> Vec<[f32; 3]> {
+    C64_COLORS.iter().map(|c| rgb_to_lab(*c)).collect()
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io::{self, Cursor, Write};
+use imageproc::image::{ImageFormat, Rgb, RgbImage};
+use crate::font::BitmapFont;
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::palette::parse_color;
+use crate::text::Cell;
+use crate::{ColorMode, meprintln};
+
+// a character with its foreground and (cell) background color
+type Glyph = (char, [u8; 3], Option<[u8; 3]>);
+
+// draws the art with a bitmap font and writes it as a png image. the font
+// is scaled up by whole pixels to get as close to --font-size as possible.
+pub struct PngFormatter{
+    font: BitmapFont,
+    scale: u32,
+    background: [u8; 3],
+    foreground: [u8; 3],
+    color: ColorMode,
+    rows: Vec<Vec<Glyph>>,
+    line: Vec<Glyph>,
+}
+
+impl PngFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        let font = options.bitmap_font.clone().unwrap_or_else(BitmapFont::builtin);
+        let scale = ((options.font_size / font.get_height() as f32).round() as u32).max(1);
+        let background = parse_color(&options.background).unwrap_or_else(|| {
+            meprintln!("WARNING: PNG output only understands colors written as #rgb or #rrggbb. black is used as the background.");
+            [0, 0, 0]
+        });
+        let foreground = parse_color(&options.foreground).unwrap_or_else(|| {
+            meprintln!("WARNING: PNG output only understands colors written as #rgb or #rrggbb. white is used as the foreground.");
+            [255, 255, 255]
+        });
+        Box::new(PngFormatter{
+            font,
+            scale,
+            background,
+            foreground,
+            color: options.color,
+            rows: Vec::new(),
+            line: Vec::new(),
+        })
+    }
+}
+
+impl OutputFormatter for PngFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        let (foreground, background) = if self.color != ColorMode::NONE {
+            (cell.rgb, cell.background)
+        }else{
+            (self.foreground, None)
+        };
+        for c in cell.glyph.chars(){
+            self.line.push((c, foreground, background));
+        }
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.rows.push(std::mem::take(&mut self.line));
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let (cell_width, cell_height) = (self.font.get_width() * self.scale, self.font.get_height() * self.scale);
+        let columns = self.rows.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+        let mut image = RgbImage::from_pixel((columns * cell_width).max(1), (self.rows.len() as u32 * cell_height).max(1), Rgb(self.background));
+        let mut bitmaps = HashMap::<char, Vec<bool>>::new();
+        for (i, row) in self.rows.iter().enumerate(){
+            for (j, (c, foreground, background)) in row.iter().enumerate(){
+                let bitmap = bitmaps.entry(*c).or_insert_with(|| self.font.render(*c));
+                let (x0, y0) = (j as u32 * cell_width, i as u32 * cell_height);
+                for y in 0..cell_height{
+                    for x in 0..cell_width{
+                        let on = bitmap[((y / self.scale) * self.font.get_width() + x / self.scale) as usize];
+                        if on {
+                            image.put_pixel(x0 + x, y0 + y, Rgb(*foreground));
+                        }else if let Some(background) = background {
+                            image.put_pixel(x0 + x, y0 + y, Rgb(*background));
+                        }
+                    }
+                }
+            }
+        }
+        let mut bytes = Cursor::new(Vec::<u8>::new());
+        image.write_to(&mut bytes, ImageFormat::Png).map_err(io::Error::other)?;
+        out.write_all(bytes.get_ref())?;
+        out.flush()
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::text::Cell;
+
+// line printer art of OVERSTRIKE output. every line is printed in as many
+// passes as its deepest stack has characters, with a carriage return between
+// them, so that each pass prints over the previous one. with --backspace
+// every character is struck over with a backspace instead, which is what
+// less and ul understand. every cell is two characters wide, like in TXT.
+pub struct PrinterFormatter{
+    stacks: Vec<Vec<char>>,
+    backspace: bool,
+    line: Vec<usize>,
+    line_buff: String,
+}
+
+impl PrinterFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(PrinterFormatter{
+            stacks: options.stacks.iter().map(|s| s.glyphs.clone()).collect(),
+            backspace: options.backspace,
+            line: Vec::new(),
+            line_buff: String::new(),
+        })
+    }
+
+    fn push_passes(&mut self){
+        let depth = self.line.iter().map(|s| self.stacks[*s].len()).max().unwrap_or(0);
+        for pass in 0..depth{
+            if pass > 0 {
+                self.line_buff.push('\r');
+            }
+            let start = self.line_buff.len();
+            for stack in &self.line{
+                let c = self.stacks[*stack].get(pass).copied().unwrap_or(' ');
+                self.line_buff.push(c);
+                self.line_buff.push(c);
+            }
+            // the head of the printer need not travel over the blank end of a pass
+            let end = start + self.line_buff[start..].trim_end_matches(' ').len();
+            self.line_buff.truncate(end);
+        }
+    }
+
+    fn push_backspaced(&mut self){
+        for stack in &self.line{
+            for _ in 0..2{
+                match self.stacks[*stack].split_first() {
+                    Some((first, rest)) => {
+                        self.line_buff.push(*first);
+                        for c in rest{
+                            self.line_buff.push('\x08');
+                            self.line_buff.push(*c);
+                        }
+                    },
+                    None => self.line_buff.push(' '),
+                }
+            }
+        }
+        let end = self.line_buff.trim_end_matches(' ').len();
+        self.line_buff.truncate(end);
+    }
+}
+
+impl OutputFormatter for PrinterFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.line.push(cell.index as usize);
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        if self.backspace {
+            self.push_backspaced();
+        }else{
+            self.push_passes();
+        }
+        self.line_buff.push('\n');
+        out.write_all(self.line_buff.as_bytes())?;
+        self.line_buff.clear();
+        self.line.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.flush()
+    }
+}
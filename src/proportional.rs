@@ -0,0 +1,175 @@
+use ab_glyph::{Font, FontVec, PxScale};
+use crate::meprintln;
+
+// how finely a row of the image is sampled, in samples per em
+pub const SAMPLES_PER_EM: u32 = 16;
+
+// how wide a glyph is and how much of the space it takes up it covers.
+// advances are given in em, densities between 0 and 1.
+#[derive(Clone, Debug)]
+pub struct GlyphMetrics{
+    pub glyph: char,
+    pub advance: f32,
+    pub density: f32,
+}
+
+// the metrics of `chars` in a truetype or opentype font, or in a metrics file
+// written one glyph per line as
+//   <character> <advance> <density>
+// where the character is the first one on the line, so it can be a space,
+// the advance is in em and the density is in any unit.
+pub fn load_metrics(path: &str, chars: &[char], line_height: f32) -> Result<Vec<GlyphMetrics>, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let is_font = [b"\x00\x01\x00\x00", b"OTTO", b"true", b"ttcf"].iter().any(|magic| data.starts_with(*magic));
+    let mut metrics = if is_font {
+        font_metrics(data, chars, line_height)?
+    }else{
+        let text = String::from_utf8(data).map_err(|_| "the metrics file is not valid utf-8")?;
+        parse_metrics(&text)?
+    };
+    if metrics.iter().all(|m| m.density == metrics[0].density) {
+        return Err(String::from("all the glyphs are equally dense"));
+    }
+    stretch_densities(&mut metrics);
+    Ok(metrics)
+}
+
+fn font_metrics(data: Vec<u8>, chars: &[char], line_height: f32) -> Result<Vec<GlyphMetrics>, String> {
+    let font = FontVec::try_from_vec(data).map_err(|e| e.to_string())?;
+    let units_per_em = font.units_per_em().ok_or("the font does not say how big its em is")?;
+    // glyphs are drawn 64 pixels high to measure how much they cover
+    let scale = PxScale::from(64.0);
+    let em = units_per_em * scale.y / font.height_unscaled();
+    let mut metrics = Vec::<GlyphMetrics>::with_capacity(chars.len());
+    for c in chars{
+        if metrics.iter().any(|m: &GlyphMetrics| m.glyph == *c) {
+            continue;
+        }
+        let id = font.glyph_id(*c);
+        if id.0 == 0 && !c.is_whitespace() {
+            meprintln!("WARNING: the font has no glyph for {:?}. it will not be used.", c);
+            continue;
+        }
+        let advance = font.h_advance_unscaled(id) / units_per_em;
+        if advance <= 0.0 {
+            continue;
+        }
+        let mut covered = 0.0;
+        if let Some(outline) = font.outline_glyph(id.with_scale(scale)) {
+            outline.draw(|_, _, coverage| covered += coverage);
+        }
+        let density = covered / (advance * em * line_height * em);
+        metrics.push(GlyphMetrics{ glyph: *c, advance, density });
+    }
+    Ok(metrics)
+}
+
+fn parse_metrics(text: &str) -> Result<Vec<GlyphMetrics>, String> {
+    let mut metrics = Vec::<GlyphMetrics>::new();
+    for (n, line) in text.lines().enumerate(){
+        let mut chars = line.chars();
+        let glyph = match chars.next() {
+            Some(c) => c,
+            None => continue,
+        };
+        let parts: Vec<&str> = chars.as_str().split_whitespace().collect();
+        if parts.len() != 2 || !chars.as_str().starts_with(char::is_whitespace) {
+            return Err(format!("line {}: expected a character, its advance and its density", n + 1));
+        }
+        let advance = parts[0].parse::<f32>().ok().filter(|a| *a > 0.0)
+            .ok_or(format!("line {}: {} is not a valid advance", n + 1, parts[0]))?;
+        let density = parts[1].parse::<f32>().ok().filter(|d| *d >= 0.0)
+            .ok_or(format!("line {}: {} is not a valid density", n + 1, parts[1]))?;
+        metrics.push(GlyphMetrics{ glyph, advance, density });
+    }
+    Ok(metrics)
+}
+
+// the sparsest glyph stands for black and the densest one for white.
+fn stretch_densities(metrics: &mut [GlyphMetrics]){
+    let low = metrics.iter().map(|m| m.density).fold(f32::MAX, f32::min);
+    let high = metrics.iter().map(|m| m.density).fold(f32::MIN, f32::max);
+    for m in metrics.iter_mut(){
+        m.density = (m.density - low) / (high - low);
+    }
+}
+
+// fills a row `width` em wide with glyphs, left to right, each time taking
+// the one whose density best matches the brightness of `samples` under it.
+// what a glyph gets wrong is carried over to the next one. the last few
+// glyphs are picked so that the row ends as close to `width` as possible,
+// which keeps the rows aligned. the result is the index of every glyph
+// together with the samples it covers.
+pub fn fill_row(samples: &[f32], width: f32, metrics: &[GlyphMetrics]) -> Vec<(usize, usize, usize)> {
+    let widest = metrics.iter().map(|m| m.advance).fold(0.0, f32::max);
+    let mut picked = Vec::<(usize, usize, usize)>::new();
+    let mut x = 0.0;
+    let mut carry = 0.0;
+    while width - x > 2.0 * widest {
+        let mut best = (0, f32::MAX, 0.0);
+        for (k, m) in metrics.iter().enumerate(){
+            let (error, next_carry) = tone_error(samples, x, m, carry);
+            if error < best.1 {
+                best = (k, error, next_carry);
+            }
+        }
+        let (start, end) = span(samples, x, metrics[best.0].advance);
+        picked.push((best.0, start, end));
+        x += metrics[best.0].advance;
+        carry = best.2;
+    }
+
+    // the end of the row, up to three glyphs
+    let mut best: (Vec<usize>, f32) = (Vec::new(), (width - x).abs() * 100.0);
+    let mut seq = Vec::<usize>::with_capacity(3);
+    close_row(samples, x, width, metrics, (carry, 0.0), &mut seq, &mut best);
+    for k in best.0{
+        let (start, end) = span(samples, x, metrics[k].advance);
+        picked.push((k, start, end));
+        x += metrics[k].advance;
+    }
+    picked
+}
+
+// tries every way of ending the row at `x` with the glyphs in `seq` and up to
+// three of them in all. `error` is the carry and the tone error of `seq`.
+fn close_row(samples: &[f32], x: f32, width: f32, metrics: &[GlyphMetrics], error: (f32, f32),
+             seq: &mut Vec<usize>, best: &mut (Vec<usize>, f32)){
+    if seq.len() == 3 {
+        return;
+    }
+    for (k, m) in metrics.iter().enumerate(){
+        let end = x + m.advance;
+        if end > width + m.advance / 2.0 {
+            continue;
+        }
+        let (tone, carry) = tone_error(samples, x, m, error.0);
+        let tone = error.1 + tone;
+        seq.push(k);
+        // being off by a little in width is worse than being off by a lot in tone
+        let cost = (width - end).abs() * 100.0 + tone;
+        if cost < best.1 {
+            *best = (seq.clone(), cost);
+        }
+        close_row(samples, end, width, metrics, (carry, tone), seq, best);
+        seq.pop();
+    }
+}
+
+// how far off `m` is from the brightness under it, weighted by its width,
+// and the error it leaves for the next glyph.
+fn tone_error(samples: &[f32], x: f32, m: &GlyphMetrics, carry: f32) -> (f32, f32) {
+    let (start, end) = span(samples, x, m.advance);
+    let brightness = samples[start..end].iter().sum::<f32>() / (end - start) as f32;
+    let target = brightness + carry / m.advance;
+    ((m.density - target).abs() * m.advance, (target - m.density) * m.advance)
+}
+
+// the samples under a glyph starting at `x`, at least one.
+fn span(samples: &[f32], x: f32, advance: f32) -> (usize, usize) {
+    let per_em = SAMPLES_PER_EM as f32;
+    let last = samples.len().max(1) - 1;
+    let start = ((x * per_em) as usize).min(last);
+    let end = (((x + advance) * per_em).round() as usize).clamp(start + 1, last + 1);
+    (start, end)
+}
@@ -0,0 +1,182 @@
+use crate::font::BitmapFont;
+
+// the ways a terminal without colors can still change how bright a character looks.
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Attribute{
+    NORMAL,
+    BOLD,
+    DIM,
+}
+
+impl Attribute{
+    pub fn parse(text: &str) -> Option<Attribute> {
+        match text.to_uppercase().as_str() {
+            "NORMAL" => Some(Attribute::NORMAL),
+            "BOLD" => Some(Attribute::BOLD),
+            "DIM" | "FAINT" => Some(Attribute::DIM),
+            _ => None,
+        }
+    }
+}
+
+// one level of a brightness ramp: a character printed with an attribute.
+#[derive(Clone, Debug)]
+pub struct RampLevel{
+    pub glyph: char,
+    pub attribute: Attribute,
+    pub brightness: f32,
+}
+
+// how much of a cell `glyph` lights up when printed with `attribute`, between 0 and 1.
+// terminals usually draw bold text one pixel wider and faint text at about
+// half the intensity, which is what is measured here.
+pub fn measure(font: &BitmapFont, glyph: char, attribute: Attribute) -> f32 {
+    let (w, h) = (font.get_width() as usize, font.get_height() as usize);
+    let bitmap = font.render(glyph);
+    let mut lit = 0;
+    for y in 0..h{
+        for x in 0..w{
+            let on = bitmap[y * w + x] || (attribute == Attribute::BOLD && x > 0 && bitmap[y * w + x - 1]);
+            if on {
+                lit += 1;
+            }
+        }
+    }
+    let coverage = lit as f32 / (w * h).max(1) as f32;
+    if attribute == Attribute::DIM { coverage / 2.0 } else { coverage }
+}
+
+// every character of `chars` in all three attributes, ordered by how bright
+// they look in `font`. levels that look the same as a brighter one with
+// fewer attributes are left out.
+pub fn auto_ramp(chars: &[char], font: &BitmapFont) -> Vec<RampLevel> {
+    let mut ramp = Vec::<RampLevel>::with_capacity(3 * chars.len());
+    for glyph in chars{
+        for attribute in [Attribute::NORMAL, Attribute::DIM, Attribute::BOLD]{
+            let brightness = measure(font, *glyph, attribute);
+            if ramp.iter().any(|l| l.glyph == *glyph && l.brightness == brightness) {
+                continue;
+            }
+            ramp.push(RampLevel{ glyph: *glyph, attribute, brightness });
+        }
+    }
+    sort_ramp(&mut ramp);
+    ramp
+}
+
+// reads a ramp written one level per line as
+//   <character> <NORMAL|BOLD|DIM> [brightness]
+// where the character is the first one on the line, so it can be a space.
+// either every level has a brightness or none has, in which case they are
+// measured in `font`.
+pub fn parse_ramp(text: &str, font: &BitmapFont) -> Result<Vec<RampLevel>, String> {
+    let mut ramp = Vec::<RampLevel>::new();
+    let mut measured = None;
+    for (n, line) in text.lines().enumerate(){
+        let mut chars = line.chars();
+        let glyph = match chars.next() {
+            Some(c) => c,
+            None => continue,
+        };
+        let rest = chars.as_str();
+        if !rest.starts_with(char::is_whitespace) {
+            return Err(format!("line {}: expected a single character followed by an attribute", n + 1));
+        }
+        let mut parts = rest.split_whitespace();
+        let attribute = parts.next()
+            .and_then(Attribute::parse)
+            .ok_or(format!("line {}: the attribute must be NORMAL, BOLD or DIM", n + 1))?;
+        let brightness = match parts.next() {
+            Some(s) => Some(s.parse::<f32>().ok().filter(|b| *b >= 0.0)
+                .ok_or(format!("line {}: {} is not a valid brightness", n + 1, s))?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(format!("line {}: too many fields", n + 1));
+        }
+        if *measured.get_or_insert(brightness.is_none()) != brightness.is_none() {
+            return Err(String::from("either all or none of the levels must be given a brightness"));
+        }
+        let brightness = brightness.unwrap_or_else(|| measure(font, glyph, attribute));
+        ramp.push(RampLevel{ glyph, attribute, brightness });
+    }
+    if ramp.len() < 2 {
+        return Err(String::from("the ramp needs at least two levels"));
+    }
+    sort_ramp(&mut ramp);
+    Ok(ramp)
+}
+
+fn sort_ramp(ramp: &mut [RampLevel]){
+    ramp.sort_by(|a, b| a.brightness.total_cmp(&b.brightness));
+}
+
+// the brightness of every level of a sorted ramp stretched over 0 to 1, for
+// use as interpolation points.
+pub fn ramp_points(brightness: &[f32]) -> Vec<f32> {
+    let first = brightness.first().copied().unwrap_or(0.0);
+    let last = brightness.last().copied().unwrap_or(1.0);
+    let span = if last > first { last - first } else { 1.0 };
+    brightness.iter().map(|b| (b - first) / span).collect()
+}
+
+// a level of a line printer ramp: characters printed on top of each other.
+// the most covering character comes last, which is the one that renderers
+// showing only one of them keep.
+#[derive(Clone, Debug)]
+pub struct Stack{
+    pub glyphs: Vec<char>,
+    pub brightness: f32,
+}
+
+// every darkness that stacks of up to `depth` characters of `chars` can print
+// in `font`, each made of as few characters as possible. the stacks are
+// ordered from the most ink to the empty one, since printers put dark ink on
+// white paper.
+pub fn overstrike_ramp(chars: &[char], depth: usize, font: &BitmapFont) -> Vec<Stack> {
+    let size = (font.get_width() * font.get_height()) as usize;
+    let mut glyphs: Vec<(char, Vec<u64>, u32)> = Vec::new();
+    for c in chars{
+        if c.is_whitespace() || glyphs.iter().any(|(g, _, _)| g == c) {
+            continue;
+        }
+        let mut bits = vec![0u64; size.div_ceil(64)];
+        for (k, on) in font.render(*c).into_iter().enumerate(){
+            if on {
+                bits[k / 64] |= 1 << (k % 64);
+            }
+        }
+        let lit = bits.iter().map(|w| w.count_ones()).sum();
+        glyphs.push((*c, bits, lit));
+    }
+    // lighter characters first, so that every stack lists its glyphs by coverage
+    glyphs.sort_by_key(|(_, _, lit)| *lit);
+
+    // the fewest characters found for every number of lit pixels
+    let mut best: Vec<Option<Vec<char>>> = vec![None; size + 1];
+    let mut stack = Vec::<char>::with_capacity(depth);
+    let empty = vec![0u64; size.div_ceil(64)];
+    collect_stacks(&glyphs, 0, depth, &empty, &mut stack, &mut best);
+
+    best.into_iter().enumerate().rev()
+        .filter_map(|(lit, stack)| stack.map(|glyphs| Stack{ glyphs, brightness: 1.0 - lit as f32 / size.max(1) as f32 }))
+        .collect()
+}
+
+fn collect_stacks(glyphs: &[(char, Vec<u64>, u32)], start: usize, depth: usize, bits: &[u64],
+                  stack: &mut Vec<char>, best: &mut [Option<Vec<char>>]){
+    let lit = bits.iter().map(|w| w.count_ones()).sum::<u32>() as usize;
+    if best[lit].as_ref().is_none_or(|b| b.len() > stack.len()) {
+        best[lit] = Some(stack.clone());
+    }
+    if stack.len() == depth {
+        return;
+    }
+    for (k, (c, glyph_bits, _)) in glyphs.iter().enumerate().skip(start){
+        let union: Vec<u64> = bits.iter().zip(glyph_bits).map(|(a, b)| a | b).collect();
+        stack.push(*c);
+        collect_stacks(glyphs, k + 1, depth, &union, stack, best);
+        stack.pop();
+    }
+}
@@ -22,12 +22,12 @@ impl SegmentInfo{
             // unused
             //in_width: in_width,
             //in_height: in_height,
-            out_width: out_width,
-            out_height: out_height,
-            w_quotient: w_quotient,
-            w_remainder: w_remainder,
-            h_quotient: h_quotient,
-            h_remainder: h_remainder,
+            out_width,
+            out_height,
+            w_quotient,
+            w_remainder,
+            h_quotient,
+            h_remainder,
         }
     }
     pub fn get_block_dims(&self, i: u32, j: u32) -> (u32, u32) {
@@ -46,16 +46,16 @@ impl SegmentInfo{
     }
     fn get_i_index(&self, i: u32) -> u32{
         if i < self.h_remainder {
-            return i * (self.h_quotient + 1);
+            i * (self.h_quotient + 1)
         }else{
-            return self.h_remainder * (self.h_quotient + 1) + (i - self.h_remainder) * self.h_quotient;
+            self.h_remainder * (self.h_quotient + 1) + (i - self.h_remainder) * self.h_quotient
         }
     }
     fn get_j_index(&self, j: u32) -> u32{
         if j < self.w_remainder {
-            return j * (self.w_quotient + 1);
+            j * (self.w_quotient + 1)
         }else{
-            return self.w_remainder * (self.w_quotient + 1) + (j - self.w_remainder) * self.w_quotient;
+            self.w_remainder * (self.w_quotient + 1) + (j - self.w_remainder) * self.w_quotient
         }
     }
     pub fn get_width(&self) -> u32 { self.out_width }
@@ -0,0 +1,160 @@
+use std::io::{self, Write};
+use crate::encoding::Encoding;
+use crate::formatter::{FormatOptions, OutputFormatter, TextFormatter, identifier};
+use crate::text::Cell;
+
+#[derive(PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+enum Language{
+    C,
+    RUST,
+    SHELL,
+}
+
+// the art as a piece of source code to paste into a program or a script.
+// every line is drawn by the TEXT formatter, so -f, -F and --color work as
+// usual, and then escaped for the language. the variable is named after --title.
+pub struct SourceFormatter{
+    language: Language,
+    name: String,
+    text: Box<dyn OutputFormatter>,
+    line_buff: Vec<u8>,
+    lines: Vec<String>,
+}
+
+impl SourceFormatter{
+    fn build(options: &FormatOptions, language: Language) -> Box<dyn OutputFormatter> {
+        // source files are utf-8, whatever --encoding says
+        let mut text_options = options.clone();
+        text_options.encoding = Encoding::UTF8;
+        let name = match language {
+            Language::RUST => identifier(&options.title, "art").to_uppercase(),
+            _ => identifier(&options.title, "art"),
+        };
+        Box::new(SourceFormatter{
+            language,
+            name,
+            text: TextFormatter::build(&text_options),
+            line_buff: Vec::new(),
+            lines: Vec::new(),
+        })
+    }
+
+    pub fn build_c(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        SourceFormatter::build(options, Language::C)
+    }
+
+    pub fn build_rust(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        SourceFormatter::build(options, Language::RUST)
+    }
+
+    pub fn build_shell(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        SourceFormatter::build(options, Language::SHELL)
+    }
+
+    fn write_c(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "const char *{}[] = {{", self.name)?;
+        for line in &self.lines{
+            let mut literal = String::new();
+            for c in line.chars(){
+                match c {
+                    '\\' => literal.push_str("\\\\"),
+                    '"' => literal.push_str("\\\""),
+                    // so that "??" never starts a trigraph
+                    '?' => literal.push_str("\\?"),
+                    '\n' => literal.push_str("\\n"),
+                    '\t' => literal.push_str("\\t"),
+                    // octal escapes end after three digits, unlike hex ones
+                    c if c.is_ascii_control() => literal.push_str(&format!("\\{:03o}", c as u32)),
+                    _ => literal.push(c),
+                }
+            }
+            writeln!(out, "    \"{}\",", literal)?;
+        }
+        writeln!(out, "}};")
+    }
+
+    fn write_rust(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "pub const {}: &[&str] = &[", self.name)?;
+        for line in &self.lines{
+            let mut literal = String::new();
+            for c in line.chars(){
+                match c {
+                    '\\' => literal.push_str("\\\\"),
+                    '"' => literal.push_str("\\\""),
+                    '\n' => literal.push_str("\\n"),
+                    '\t' => literal.push_str("\\t"),
+                    c if c.is_control() => literal.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                    _ => literal.push(c),
+                }
+            }
+            writeln!(out, "    \"{}\",", literal)?;
+        }
+        writeln!(out, "];")
+    }
+
+    fn write_shell(&self, out: &mut dyn Write) -> io::Result<()> {
+        // the here-document must not contain its own delimiter
+        let mut delimiter = String::from("EOF");
+        while self.lines.iter().any(|line| line.split('\n').any(|l| l == delimiter)) {
+            delimiter.push('_');
+        }
+        // a quoted here-document is taken as is, but escape sequences can not
+        // be written in it. with colors, printf %b turns them into escapes,
+        // which means the backslashes of the art have to be escaped too.
+        let escapes = self.lines.iter().any(|line| line.chars().any(|c| c.is_ascii_control() && c != '\n' && c != '\t'));
+        if escapes {
+            writeln!(out, "printf '%b\\n' \"$(cat <<'{}'", delimiter)?;
+        }else{
+            writeln!(out, "cat <<'{}'", delimiter)?;
+        }
+        for line in &self.lines{
+            if escapes {
+                let mut escaped = String::new();
+                for c in line.chars(){
+                    match c {
+                        '\\' => escaped.push_str("\\\\"),
+                        c if c.is_ascii_control() && c != '\n' && c != '\t' => escaped.push_str(&format!("\\0{:03o}", c as u32)),
+                        _ => escaped.push(c),
+                    }
+                }
+                writeln!(out, "{}", escaped)?;
+            }else{
+                writeln!(out, "{}", line)?;
+            }
+        }
+        writeln!(out, "{}", delimiter)?;
+        if escapes {
+            writeln!(out, ")\"")?;
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormatter for SourceFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, width: u32, height: u32) -> io::Result<()> {
+        self.text.begin(&mut self.line_buff, width, height)
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        self.text.cell(&mut self.line_buff, cell)
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, row: u32) -> io::Result<()> {
+        self.text.end_of_line(&mut self.line_buff, row)?;
+        let line = String::from_utf8_lossy(&self.line_buff);
+        // every line is a string of its own, the newline is implied
+        self.lines.push(String::from(line.strip_suffix('\n').unwrap_or(&line)));
+        self.line_buff.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        match self.language {
+            Language::C => self.write_c(out)?,
+            Language::RUST => self.write_rust(out)?,
+            Language::SHELL => self.write_shell(out)?,
+        }
+        out.flush()
+    }
+}
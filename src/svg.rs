@@ -0,0 +1,112 @@
+use std::io::{self, Write};
+use crate::formatter::{FormatOptions, OutputFormatter};
+use crate::html::escape_html;
+use crate::text::{Cell, is_blank};
+use crate::ColorMode;
+
+// the width of a character of a monospace font relative to its size. there is
+// no way to measure the font from here, and this is what most of them use.
+const ADVANCE: f32 = 0.6;
+
+// a scalable image with one <tspan> per row. with --color every run of
+// equally colored characters gets a <tspan> of its own and cell backgrounds
+// are drawn as rectangles behind the text.
+pub struct SvgFormatter{
+    font: String,
+    font_size: f32,
+    line_height: f32,
+    background: String,
+    foreground: String,
+    color: ColorMode,
+    run_color: Option<[u8; 3]>,
+    column: usize,
+    columns: usize,
+    rows: Vec<String>,
+    rects: Vec<String>,
+    line_buff: String,
+}
+
+impl SvgFormatter{
+    pub fn build(options: &FormatOptions) -> Box<dyn OutputFormatter> {
+        Box::new(SvgFormatter{
+            font: options.font.clone(),
+            font_size: options.font_size,
+            line_height: options.font_size * options.line_height,
+            background: options.background.clone(),
+            foreground: options.foreground.clone(),
+            color: options.color,
+            run_color: None,
+            column: 0,
+            columns: 0,
+            rows: Vec::new(),
+            rects: Vec::new(),
+            line_buff: String::new(),
+        })
+    }
+
+    fn close_run(&mut self){
+        if self.run_color.take().is_some() {
+            self.line_buff.push_str("</tspan>");
+        }
+    }
+}
+
+impl OutputFormatter for SvgFormatter{
+    fn begin(&mut self, _out: &mut dyn Write, _width: u32, _height: u32) -> io::Result<()> {
+        // the header needs the size of the image, so everything is written in finish
+        Ok(())
+    }
+
+    fn cell(&mut self, _out: &mut dyn Write, cell: &Cell) -> io::Result<()> {
+        let length = cell.width();
+        if self.color != ColorMode::NONE {
+            if let Some([r, g, b]) = cell.background {
+                self.rects.push(format!("<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+                                        self.column as f32 * ADVANCE * self.font_size, self.rows.len() as f32 * self.line_height,
+                                        length as f32 * ADVANCE * self.font_size, self.line_height, r, g, b));
+            }
+            if self.run_color != Some(cell.rgb) && !is_blank(&cell.glyph) {
+                self.close_run();
+                let [r, g, b] = cell.rgb;
+                self.line_buff.push_str(&format!("<tspan fill=\"#{:02x}{:02x}{:02x}\">", r, g, b));
+                self.run_color = Some(cell.rgb);
+            }
+        }
+        escape_html(&cell.glyph, &mut self.line_buff);
+        self.column += length;
+        Ok(())
+    }
+
+    fn end_of_line(&mut self, _out: &mut dyn Write, _row: u32) -> io::Result<()> {
+        self.close_run();
+        // put the baseline where a font with the usual ascent of 0.8em would be centered on the line
+        let baseline = self.rows.len() as f32 * self.line_height + (self.line_height - self.font_size) / 2.0 + 0.8 * self.font_size;
+        self.rows.push(format!("<tspan x=\"0\" y=\"{:.2}\">{}</tspan>", baseline, self.line_buff));
+        self.line_buff.clear();
+        self.columns = self.columns.max(self.column);
+        self.column = 0;
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let width = self.columns as f32 * ADVANCE * self.font_size;
+        let height = self.rows.len() as f32 * self.line_height;
+        let (mut font, mut background, mut foreground) = (String::new(), String::new(), String::new());
+        escape_html(&self.font, &mut font);
+        escape_html(&self.background, &mut background);
+        escape_html(&self.foreground, &mut foreground);
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.2}\" height=\"{:.2}\" viewBox=\"0 0 {:.2} {:.2}\">", width, height, width, height)?;
+        writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", background)?;
+        for rect in &self.rects{
+            writeln!(out, "{}", rect)?;
+        }
+        writeln!(out, "<text font-family=\"{}\" font-size=\"{:.2}\" fill=\"{}\" xml:space=\"preserve\">", font, self.font_size, foreground)?;
+        for row in &self.rows{
+            writeln!(out, "{}", row)?;
+        }
+        writeln!(out, "</text>")?;
+        writeln!(out, "</svg>")?;
+        out.flush()
+    }
+}
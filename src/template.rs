@@ -0,0 +1,147 @@
+use std::fmt::Write;
+use crate::text::Cell;
+
+// placeholders understood by the format strings:
+//   character format (-f): {} or {c}   the character(s) of the cell
+//                          {v}         source gray value (0-255)
+//                          {i} {row}   row of the cell
+//                          {j} {col}   column of the cell
+//                          {r} {g} {b} average color of the source block (0-255)
+//                          {hex}       the same color as rrggbb
+//   line format (-F):      {} or {l}   the formatted characters of the line
+//                          {i} {row}   row of the line
+// "{{" and "}}" produce literal braces. \n, \t, \r, \e (escape) and \\ are
+// also recognized so that they can be typed on the command line.
+
+#[derive(Clone, PartialEq)]
+enum Token{
+    Literal(String),
+    Char,
+    Gray,
+    Row,
+    Col,
+    Red,
+    Green,
+    Blue,
+    Hex,
+    Line,
+}
+
+#[derive(Clone, PartialEq)]
+enum TemplateKind{
+    Cell,
+    Line,
+}
+
+#[derive(Clone)]
+pub struct Template{
+    tokens: Vec<Token>,
+}
+
+impl Template{
+    pub fn parse_cell(src: &str) -> Result<Template, String> {
+        Template::parse(src, TemplateKind::Cell)
+    }
+
+    pub fn parse_line(src: &str) -> Result<Template, String> {
+        Template::parse(src, TemplateKind::Line)
+    }
+
+    fn parse(src: &str, kind: TemplateKind) -> Result<Template, String> {
+        let mut tokens = Vec::<Token>::new();
+        let mut literal = String::new();
+        let mut chars = src.chars().peekable();
+        while let Some(c) = chars.next(){
+            match c {
+                '\\' => {
+                    match chars.next(){
+                        Some('n') => literal.push('\n'),
+                        Some('t') => literal.push('\t'),
+                        Some('r') => literal.push('\r'),
+                        Some('e') => literal.push('\x1b'),
+                        Some('\\') => literal.push('\\'),
+                        Some(other) => {
+                            literal.push('\\');
+                            literal.push(other);
+                        },
+                        None => literal.push('\\'),
+                    }
+                },
+                '{' => {
+                    if chars.peek() == Some(&'{') {
+                        chars.next();
+                        literal.push('{');
+                        continue;
+                    }
+                    let mut name = String::new();
+                    loop {
+                        match chars.next(){
+                            Some('}') => break,
+                            Some(n) => name.push(n),
+                            None => return Err(format!("unterminated placeholder \"{{{}\" in format string", name)),
+                        }
+                    }
+                    let token = match (&kind, name.trim()) {
+                        (TemplateKind::Cell, "" | "c") => Token::Char,
+                        (TemplateKind::Cell, "v") => Token::Gray,
+                        (TemplateKind::Cell, "j" | "col") => Token::Col,
+                        (TemplateKind::Cell, "r") => Token::Red,
+                        (TemplateKind::Cell, "g") => Token::Green,
+                        (TemplateKind::Cell, "b") => Token::Blue,
+                        (TemplateKind::Cell, "hex") => Token::Hex,
+                        (TemplateKind::Line, "" | "l") => Token::Line,
+                        (_, "i" | "row") => Token::Row,
+                        (_, other) => return Err(format!("unknown placeholder \"{{{}}}\" in format string", other)),
+                    };
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(token);
+                },
+                '}' => {
+                    if chars.peek() == Some(&'}') {
+                        chars.next();
+                        literal.push('}');
+                        continue;
+                    }
+                    return Err(String::from("unmatched \"}\" in format string. use \"}}\" for a literal brace"));
+                },
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(Template{ tokens })
+    }
+
+    pub fn render_cell(&self, cell: &Cell, output_buff: &mut String){
+        let [r, g, b] = cell.rgb;
+        for token in &self.tokens{
+            // writing into a String never fails
+            let _ = match token {
+                Token::Literal(s) => write!(output_buff, "{}", s),
+                Token::Char => write!(output_buff, "{}", cell.glyph),
+                Token::Gray => write!(output_buff, "{}", (cell.gray.clamp(0.0, 1.0) * 255.0).round() as u8),
+                Token::Row => write!(output_buff, "{}", cell.row),
+                Token::Col => write!(output_buff, "{}", cell.col),
+                Token::Red => write!(output_buff, "{}", r),
+                Token::Green => write!(output_buff, "{}", g),
+                Token::Blue => write!(output_buff, "{}", b),
+                Token::Hex => write!(output_buff, "{:02x}{:02x}{:02x}", r, g, b),
+                Token::Line => Ok(()),
+            };
+        }
+    }
+
+    pub fn render_line(&self, line: &str, row: u32, output_buff: &mut String){
+        for token in &self.tokens{
+            let _ = match token {
+                Token::Literal(s) => write!(output_buff, "{}", s),
+                Token::Line => write!(output_buff, "{}", line),
+                Token::Row => write!(output_buff, "{}", row),
+                _ => Ok(()),
+            };
+        }
+    }
+}
@@ -1,18 +1,175 @@
 use std::fs::File;
-use std::io::{Write};
+use std::io::{self, Write, BufWriter};
 use crate::matrix::Matrix;
-use crate::{ProgType, CharsOption, DithType};
+use crate::formatter::OutputFormatter;
+use crate::palette::{Palette, rgb_to_lab};
+use crate::petscii::{self, CELL_SIZE, C64_COLORS};
+use crate::emoji::{EMOJI, emoji_palette};
+use crate::ramp::{Attribute, RampLevel, Stack};
+use crate::proportional::{GlyphMetrics, fill_row};
+use crate::{ProgType, CharsOption, DithType, ColorMode, meprintln};
 
 pub const DEFAULT_CHARS_LEN: usize = 39;
 const DEFAULT_CHARS: [char; DEFAULT_CHARS_LEN] = [' ','.','`','\'','-','~','+','^',':',';','>','<','?',')','(','|',']','[','}','{','\\','/',
                                                   'i','1','l','L','0','O','m','q','d','k','#','W','%','&','B','@','$'];
 
-pub fn print_output(matrix: Matrix<f32>, _fmt_str: String, _fmt_ln_str: String, chars: CharsOption, out_type: ProgType, dith_type: DithType, output: Option<File>){
-    // array of characters, arranged in increasing brightness
-    let char_array: Vec<char> = match chars{
+// one output character together with the information about the part of the
+// image it was made from. this is what the format strings get to see.
+// `index` is the number the glyph was picked by: its position in the
+// character array for TXT, the bit pattern of the pixels for the others.
+pub struct Cell{
+    pub glyph: String,
+    pub index: u32,
+    pub gray: f32,
+    pub rgb: [u8; 3],
+    pub background: Option<[u8; 3]>,
+    pub attribute: Attribute,
+    pub row: u32,
+    pub col: u32,
+    // emoji take two columns of the terminal for a single char
+    pub wide: bool,
+}
+
+impl Cell{
+    // the number of columns the glyph takes on screen
+    pub fn width(&self) -> usize {
+        if self.wide { 2 } else { self.glyph.chars().count() }
+    }
+}
+
+// the color of a blank cell does not show, so formatters need not switch colors for it.
+pub fn is_blank(glyph: &str) -> bool {
+    glyph.chars().all(char::is_whitespace)
+}
+
+// the rows of one picture. still images have a single frame, animations one
+// per frame of the source, each shown for `delay` seconds.
+pub struct Frame{
+    pub rows: Vec<Vec<Cell>>,
+    pub delay: f32,
+}
+
+pub fn build_rows(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, chars: &CharsOption, out_type: &ProgType, dith_type: DithType, color: ColorMode) -> Vec<Vec<Cell>> {
+    let char_array = get_char_array(chars);
+
+    match out_type{
+        ProgType::TXT | ProgType::OVERSTRIKE | ProgType::PROPORTIONAL => {
+            produce_buffer_txt(matrix, source, colors, char_array, dith_type)
+        },
+        ProgType::BRAILE => {
+            produce_buffer_braile(matrix, source, colors)
+        },
+        ProgType::HALFBLOCK => {
+            produce_buffer_halfblock(matrix, source, colors, color != ColorMode::NONE)
+        },
+        ProgType::QUADRANT => {
+            produce_buffer_quadrant(matrix, source, colors)
+        },
+        ProgType::SEXTANT => {
+            produce_buffer_sextant(matrix, source, colors)
+        },
+        ProgType::OCTANT => {
+            produce_buffer_octant(matrix, source, colors)
+        },
+        ProgType::PETSCII => {
+            produce_buffer_petscii(source, colors)
+        },
+        ProgType::PIXEL => {
+            produce_buffer_pixel(matrix, source, colors)
+        },
+        ProgType::EMOJI => {
+            produce_buffer_emoji(source, colors)
+        }
+    }
+}
+
+// TXT rows that take their characters and attributes from a ramp instead of
+// the character array. `matrix` holds the ramp levels picked by the
+// interpolating ditherer.
+pub fn build_rows_ramp(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, ramp: &[RampLevel]) -> Vec<Vec<Cell>> {
+    let glyphs: Vec<char> = ramp.iter().map(|l| l.glyph).collect();
+    let mut rows = produce_buffer_txt(matrix, source, colors, glyphs, DithType::INTER);
+    for cell in rows.iter_mut().flatten(){
+        cell.attribute = ramp[cell.index as usize].attribute;
+    }
+    rows
+}
+
+// OVERSTRIKE rows, where `matrix` holds the stacks picked by the ditherer.
+// a cell shows the last character of its stack, the whole stack is left to
+// the PRINTER output.
+pub fn build_rows_overstrike(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, stacks: &[Stack], dith_type: DithType) -> Vec<Vec<Cell>> {
+    let glyphs: Vec<char> = stacks.iter().map(|s| s.glyphs.last().copied().unwrap_or(' ')).collect();
+    produce_buffer_txt(matrix, source, colors, glyphs, dith_type)
+}
+
+// PROPORTIONAL rows, each `width` em wide. `source` and `colors` have
+// SAMPLES_PER_EM entries for every em of a row. the rows are as wide as each
+// other in the font, but hold different numbers of cells.
+pub fn build_rows_proportional(source: Matrix<f32>, colors: Matrix<[u8; 3]>, metrics: &[GlyphMetrics], width: f32) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(source.get_height() as usize);
+    for i in 0..source.get_height(){
+        let samples: Vec<f32> = (0..source.get_width()).map(|j| source.get(i, j).unwrap()).collect();
+        let mut row = Vec::<Cell>::new();
+        for (j, (index, start, end)) in fill_row(&samples, width, metrics).into_iter().enumerate(){
+            let mut rgb: [u32; 3] = [0, 0, 0];
+            for x in start..end{
+                for (acc, c) in rgb.iter_mut().zip(colors.get(i, x as u32).unwrap()){
+                    *acc += c as u32;
+                }
+            }
+            row.push(Cell{
+                glyph: String::from(metrics[index].glyph),
+                index: index as u32,
+                gray: samples[start..end].iter().sum::<f32>() / (end - start) as f32,
+                rgb: rgb.map(|c| (c / (end - start) as u32) as u8),
+                background: None,
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j as u32,
+                wide: false,
+            });
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+// snap the colors to what the terminal can show
+pub fn quantize_rows(rows: &mut [Vec<Cell>], color: ColorMode){
+    if let Some(palette) = Palette::for_mode(color) {
+        for cell in rows.iter_mut().flatten(){
+            cell.rgb = palette.quantize(cell.rgb);
+            cell.background = cell.background.map(|c| palette.quantize(c));
+        }
+    }
+}
+
+pub fn print_output(frames: Vec<Frame>, mut formatter: Box<dyn OutputFormatter>, output: Option<File>){
+    // select output
+    let newline = output.is_none() && formatter.trailing_newline();
+    let mut out: Box<dyn Write> = match output {
+        Some(f) => Box::new(BufWriter::new(f)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let written = write_frames(&frames, formatter.as_mut(), out.as_mut())
+        .and_then(|()| if newline { out.write_all(b"\n") } else { Ok(()) });
+    match written {
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+        Err(e) => {
+            meprintln!("Error while writing the output: {}", e);
+        }
+    }
+}
+
+// array of characters, arranged in increasing brightness
+pub fn get_char_array(chars: &CharsOption) -> Vec<char> {
+    match chars{
         Some(s) => {
             let mut starts_with_space = false;
-            if s.chars().nth(0).unwrap() == ' ' {starts_with_space = true;}
+            if s.starts_with(' ') {starts_with_space = true;}
             let temp = s.trim().replace("\n", "");
             let mut temp: Vec<char> = temp.chars().collect();
             if starts_with_space {
@@ -23,33 +180,45 @@ pub fn print_output(matrix: Matrix<f32>, _fmt_str: String, _fmt_ln_str: String,
         None => {
             Vec::<char>::from(DEFAULT_CHARS)
         }
-    };
-    // output buffer
-    let mut output_buff = String::new();
+    }
+}
 
+// every glyph an output type can produce, in the order of Cell::index.
+pub fn get_glyph_table(out_type: &ProgType, char_array: &[char]) -> Vec<char> {
     match out_type{
-        ProgType::TXT => {
-            produce_buffer_txt(matrix, char_array, dith_type, &mut output_buff);
-        },
-        ProgType::BRAILE => {
-            produce_buffer_braile(matrix, &mut output_buff);
-        }
+        ProgType::TXT | ProgType::OVERSTRIKE | ProgType::PROPORTIONAL => char_array.to_vec(),
+        ProgType::BRAILE => (0..256).map(|n| char::from_u32(10240 + n).unwrap()).collect(),
+        ProgType::HALFBLOCK => HALFBLOCKS.to_vec(),
+        ProgType::QUADRANT => QUADRANTS.to_vec(),
+        ProgType::SEXTANT => (0..64).map(sextant_char).collect(),
+        ProgType::OCTANT => (0..256).map(octant_char).collect(),
+        ProgType::PETSCII => (0..=255).map(petscii::petscii_char).collect(),
+        ProgType::PIXEL => PIXELS.to_vec(),
+        ProgType::EMOJI => EMOJI.iter().map(|(c, _)| *c).collect(),
     }
+}
 
-    // select output and write
-    match output {
-        Some(mut f) => {
-            write!(f, "{}", output_buff).unwrap();
-        },
-        None => {
-            println!("{}", output_buff);
+fn write_frames(frames: &[Frame], formatter: &mut dyn OutputFormatter, out: &mut dyn Write) -> io::Result<()> {
+    let rows = frames.first().map_or(&[] as &[Vec<Cell>], |frame| &frame.rows);
+    let width = rows.first().map_or(0, |row| row.len() as u32);
+    formatter.begin(out, width, rows.len() as u32)?;
+    for frame in frames{
+        for (i, row) in frame.rows.iter().enumerate(){
+            for cell in row{
+                formatter.cell(out, cell)?;
+            }
+            formatter.end_of_line(out, i as u32)?;
         }
-    };
+        formatter.end_of_frame(out, frame.delay)?;
+    }
+    formatter.finish(out)
 }
 
-fn produce_buffer_txt(matrix: Matrix<f32>, char_array: Vec<char>, dith_type: DithType, output_buff: &mut String) {
-    // cast all matrix entries to characters, then format them and add to buffer
+fn produce_buffer_txt(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, char_array: Vec<char>, dith_type: DithType) -> Vec<Vec<Cell>> {
+    // cast all matrix entries to characters
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(matrix.get_height() as usize);
     for i in 0..matrix.get_height(){
+        let mut row = Vec::<Cell>::with_capacity(matrix.get_width() as usize);
         for j in 0..matrix.get_width(){
             let val = matrix.get(i, j).unwrap();
             let index: usize = match dith_type {
@@ -62,25 +231,231 @@ fn produce_buffer_txt(matrix: Matrix<f32>, char_array: Vec<char>, dith_type: Dit
             };
             let index = if index == char_array.len() {char_array.len() - 1} else {index};
             let out_char = char_array[index];
-            output_buff.push(out_char);
-            output_buff.push(out_char);
+            // every character is printed twice to make up for the aspect ratio of the font
+            row.push(Cell{
+                glyph: String::from_iter([out_char, out_char]),
+                index: index as u32,
+                gray: source.get(i, j).unwrap(),
+                rgb: colors.get(i, j).unwrap(),
+                background: None,
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j,
+                wide: false,
+            });
         }
-        output_buff.push('\n');
+        rows.push(row);
     }
+    rows
 }
 
-fn produce_buffer_braile(matrix: Matrix<f32>, output_buff: &mut String) {
-    let lx = matrix.get_width() / 2;
-    let ly = matrix.get_height() / 4;
+fn produce_buffer_braile(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let passes = [(0,0,0), (0,1,1), (0,2,2), (1,0,3), (1,1,4), (1,2,5), (0,3,6), (1,3,7)];
+    pack_cells(matrix, source, colors, (2, 4), &passes, |charnum| char::from_u32(10240 + charnum).unwrap())
+}
+
+// the quadrant characters in the order of their bit patterns
+pub const QUADRANTS: [char; 16] = [' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'];
+
+fn produce_buffer_quadrant(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let passes = [(0,0,0), (1,0,1), (0,1,2), (1,1,3)];
+    pack_cells(matrix, source, colors, (2, 2), &passes, |charnum| QUADRANTS[charnum as usize])
+}
+
+fn produce_buffer_sextant(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let passes = [(0,0,0), (1,0,1), (0,1,2), (1,1,3), (0,2,4), (1,2,5)];
+    pack_cells(matrix, source, colors, (2, 3), &passes, sextant_char)
+}
+
+fn produce_buffer_octant(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let passes = [(0,0,0), (1,0,1), (0,1,2), (1,1,3), (0,2,4), (1,2,5), (0,3,6), (1,3,7)];
+    pack_cells(matrix, source, colors, (2, 4), &passes, octant_char)
+}
+
+// the sextants (U+1FB00..U+1FB3B) are in the order of their bit patterns,
+// except for the ones that already exist elsewhere.
+pub fn sextant_char(charnum: u32) -> char {
+    match charnum {
+        0 => ' ',
+        21 => '▌',
+        42 => '▐',
+        63 => '█',
+        n => char::from_u32(0x1FB00 + n - 1 - (n > 21) as u32 - (n > 42) as u32).unwrap(),
+    }
+}
+
+// the same goes for the octants (U+1CD00..U+1CDE5), only there are more of
+// the patterns that already had a character of their own.
+pub fn octant_char(charnum: u32) -> char {
+    const EXISTING: [(u32, char); 26] = [
+        (0, ' '), (1, '\u{1CEA8}'), (2, '\u{1CEAB}'), (3, '\u{1FB82}'), (5, '▘'), (10, '▝'), (15, '▀'),
+        (20, '\u{1FBE6}'), (40, '\u{1FBE7}'), (63, '\u{1FB85}'), (64, '\u{1CEA3}'), (80, '▖'), (85, '▌'),
+        (90, '▞'), (95, '▛'), (128, '\u{1CEA0}'), (160, '▗'), (165, '▚'), (170, '▐'), (175, '▜'),
+        (192, '▂'), (240, '▄'), (245, '▙'), (250, '▟'), (252, '▆'), (255, '█'),
+    ];
+    match EXISTING.binary_search_by_key(&charnum, |(n, _)| *n) {
+        Ok(i) => EXISTING[i].1,
+        Err(skipped) => char::from_u32(0x1CD00 + charnum - skipped as u32).unwrap(),
+    }
+}
+
+// packs every cell_width x cell_height block of the (dithered) matrix into a
+// single character. each pass sets bit `shift` of the character number if the
+// pixel at (dx, dy) is on.
+fn pack_cells(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, cell_dims: (u32, u32),
+              passes: &[(u32, u32, u32)], glyph: impl Fn(u32) -> char) -> Vec<Vec<Cell>> {
+    let (cell_width, cell_height) = cell_dims;
+    let lx = matrix.get_width() / cell_width;
+    let ly = matrix.get_height() / cell_height;
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(ly as usize);
     for i in 0..ly{
+        let mut row = Vec::<Cell>::with_capacity(lx as usize);
         for j in 0..lx{
-            let mut charnum: u32= 10240;
-            let passes = [(0,0,0), (0,1,1), (0,2,2), (1,0,3), (1,1,4), (1,2,5), (0,3,6), (1,3,7)];
+            let mut charnum: u32 = 0;
+            let mut gray: f32 = 0.0;
+            let mut rgb: [u32; 3] = [0, 0, 0];
             for (dx, dy, shift) in passes{
-                charnum += (matrix.get(4*i + dy , 2*j + dx).unwrap() as u32) << shift;
+                let (y, x) = (cell_height*i + dy, cell_width*j + dx);
+                charnum += (matrix.get(y, x).unwrap() as u32) << shift;
+                gray += source.get(y, x).unwrap();
+                for (acc, c) in rgb.iter_mut().zip(colors.get(y, x).unwrap()){
+                    *acc += c as u32;
+                }
+            }
+            row.push(Cell{
+                glyph: String::from(glyph(charnum)),
+                index: charnum,
+                gray: gray / (passes.len() as f32),
+                rgb: rgb.map(|c| (c / (passes.len() as u32)) as u8),
+                background: None,
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j,
+                wide: false,
+            });
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+pub const PIXELS: [char; 2] = [' ', '█'];
+
+// one cell for every dithered pixel.
+fn produce_buffer_pixel(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    pack_cells(matrix, source, colors, (1, 1), &[(0, 0, 0)], |charnum| PIXELS[charnum as usize])
+}
+
+// every cell is the emoji whose mean color is closest to the average color
+// of its part of the image. emoji are two columns wide, like a TXT cell.
+fn produce_buffer_emoji(source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let palette = emoji_palette();
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(colors.get_height() as usize);
+    for i in 0..colors.get_height(){
+        let mut row = Vec::<Cell>::with_capacity(colors.get_width() as usize);
+        for j in 0..colors.get_width(){
+            let rgb = colors.get(i, j).unwrap();
+            let index = palette.nearest(rgb);
+            row.push(Cell{
+                glyph: String::from(EMOJI[index].0),
+                index: index as u32,
+                gray: source.get(i, j).unwrap(),
+                rgb,
+                background: None,
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j,
+                wide: true,
+            });
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+pub const HALFBLOCKS: [char; 4] = [' ', '▄', '▀', '█'];
+
+// every cell holds two pixels on top of each other. with colors the upper one
+// is the foreground of '▀' and the lower one its background, otherwise the
+// dithered pixels pick one of ' ', '▄', '▀' and '█'.
+fn produce_buffer_halfblock(matrix: Matrix<f32>, source: Matrix<f32>, colors: Matrix<[u8; 3]>, colored: bool) -> Vec<Vec<Cell>> {
+    let lx = matrix.get_width();
+    let ly = matrix.get_height() / 2;
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(ly as usize);
+    for i in 0..ly{
+        let mut row = Vec::<Cell>::with_capacity(lx as usize);
+        for j in 0..lx{
+            let upper = colors.get(2*i, j).unwrap();
+            let lower = colors.get(2*i + 1, j).unwrap();
+            let (index, rgb, background) = if colored {
+                (2, upper, Some(lower))
+            }else{
+                let index = ((matrix.get(2*i, j).unwrap() as usize) << 1) + (matrix.get(2*i + 1, j).unwrap() as usize);
+                let rgb = [0, 1, 2].map(|k| ((upper[k] as u32 + lower[k] as u32) / 2) as u8);
+                (index, rgb, None)
+            };
+            row.push(Cell{
+                glyph: String::from(HALFBLOCKS[index]),
+                index: index as u32,
+                gray: (source.get(2*i, j).unwrap() + source.get(2*i + 1, j).unwrap()) / 2.0,
+                rgb,
+                background,
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j,
+                wide: false,
+            });
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+// every cell is an 8x8 block of pixels that gets the petscii glyph and the
+// c64 color matching it best, drawn on one background color for the whole
+// screen like the c64 does in its text mode.
+fn produce_buffer_petscii(source: Matrix<f32>, colors: Matrix<[u8; 3]>) -> Vec<Vec<Cell>> {
+    let lx = colors.get_width() / CELL_SIZE;
+    let ly = colors.get_height() / CELL_SIZE;
+    let palette_lab = petscii::palette_lab();
+    let bitmaps = petscii::glyph_bitmaps();
+
+    let mut pixels = Vec::<[f32; 3]>::with_capacity((colors.get_width() * colors.get_height()) as usize);
+    for y in 0..colors.get_height(){
+        for x in 0..colors.get_width(){
+            pixels.push(rgb_to_lab(colors.get(y, x).unwrap()));
+        }
+    }
+    let background = petscii::pick_background(&pixels, &palette_lab);
+
+    let mut rows = Vec::<Vec<Cell>>::with_capacity(ly as usize);
+    let mut cell_pixels = Vec::<[f32; 3]>::with_capacity((CELL_SIZE * CELL_SIZE) as usize);
+    for i in 0..ly{
+        let mut row = Vec::<Cell>::with_capacity(lx as usize);
+        for j in 0..lx{
+            cell_pixels.clear();
+            let mut gray: f32 = 0.0;
+            for dy in 0..CELL_SIZE{
+                for dx in 0..CELL_SIZE{
+                    let (y, x) = (CELL_SIZE*i + dy, CELL_SIZE*j + dx);
+                    cell_pixels.push(pixels[(y * colors.get_width() + x) as usize]);
+                    gray += source.get(y, x).unwrap();
+                }
             }
-            output_buff.push(char::from_u32(charnum).unwrap());
+            let (code, color) = petscii::match_cell(&cell_pixels, background, &bitmaps, &palette_lab);
+            row.push(Cell{
+                glyph: String::from(petscii::petscii_char(code)),
+                index: code as u32,
+                gray: gray / (CELL_SIZE * CELL_SIZE) as f32,
+                rgb: C64_COLORS[color],
+                background: Some(C64_COLORS[background]),
+                attribute: Attribute::NORMAL,
+                row: i,
+                col: j,
+                wide: false,
+            });
         }
-        output_buff.push('\n');
+        rows.push(row);
     }
+    rows
 }